@@ -77,4 +77,118 @@ pub enum SolX404Error {
     // 23 - Not a valid deposit user
     #[msg("InvalidDepositer")]
     InvalidDepositer,
+    // 24 - NFT is not in the owner store
+    #[msg("NFT is not assigned to any holder")]
+    NFTNotAssigned,
+    // 25 - NFT is assigned to another holder
+    #[msg("NFT is not held by the signer")]
+    NotNFTHolder,
+    // 26 - exemption list is not owned by the program
+    #[msg("Invalid exemption list")]
+    InvalidExemptionList,
+    // 27 - holder config is not owned by the program
+    #[msg("Invalid holder config")]
+    InvalidHolderConfig,
+    // 28 - u64 or usize overflow, or division by a zero fungible supply
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    // 29 - u64 underflow
+    #[msg("Arithmetic underflow")]
+    ArithmeticUnderflow,
+    // 30 - NFTs assigned to a holder do not match its fungible balance
+    #[msg("NFT assignment does not match the fungible balance")]
+    NFTBalanceMismatch,
+    // 31 - hook extra account is not the meta list PDA of the hook program
+    #[msg("Invalid hook extra account")]
+    InvalidHookExtraAccount,
+    // 32 - hook program is not in the allowlist of the hub
+    #[msg("Hook program is not approved")]
+    HookProgramNotApproved,
+    // 33 - deposit or issued NFT is already linked
+    #[msg("NFT already linked")]
+    NFTAlreadyLinked,
+    // 34 - deposited NFT has no valid metaplex metadata
+    #[msg("Invalid deposit metadata")]
+    InvalidDepositMetadata,
+    // 35 - linked NFT does not match the bank
+    #[msg("Invalid linked NFT")]
+    InvalidLinkedNFT,
+    // 36 - the reveal already happened
+    #[msg("NFT already revealed")]
+    AlreadyRevealed,
+    // 37 - no reveal hash committed for the state
+    #[msg("Reveal not configured")]
+    RevealNotConfigured,
+    // 38 - the base uri is not published yet
+    #[msg("NFT not revealed")]
+    NotRevealed,
+    // 39 - merkle proof does not match the reveal hash
+    #[msg("Invalid reveal proof")]
+    InvalidRevealProof,
+    // 40 - uri longer than the space reserved in the state
+    #[msg("Uri too long")]
+    UriTooLong,
+    // 41 - no NFT in the pool of the state
+    #[msg("Pool is empty")]
+    EmptyPool,
+    // 42 - randomness account differs from the state configuration
+    #[msg("Invalid randomness source")]
+    InvalidRandomness,
+    // 43 - royalty above 100%
+    #[msg("Invalid royalty")]
+    InvalidRoyalty,
+    // 44 - marketplace allowlist longer than the space reserved in the state
+    #[msg("Too many marketplaces")]
+    TooManyMarketplaces,
+    // 45 - bound registry account is not owned by the program
+    #[msg("Invalid bound registry")]
+    InvalidBoundRegistry,
+    // 46 - NFT is not recorded in the bound registry
+    #[msg("NFT not bound")]
+    NFTNotBound,
+    // 47 - signer is not the holder recorded for the bound NFT
+    #[msg("Not bound holder")]
+    NotBoundHolder,
+    // 48 - caller is not the meta list of the NFT mint
+    #[msg("Only call by NFT hook")]
+    OnlyCallByNFTHook,
+    // 49 - remaining accounts are not the mint and token account of every number
+    #[msg("Invalid batch accounts")]
+    InvalidBatchAccounts,
+    // 50 - remaining accounts are not pairs of an issued NFT mint and one of its token accounts
+    #[msg("Invalid auto bind accounts")]
+    InvalidAutoBindAccounts,
+    // 51 - NFT is already recorded in the bound registry
+    #[msg("NFT already bound")]
+    NFTAlreadyBound,
+    // 52 - token account of the signer does not hold the bound NFT
+    #[msg("Bound NFT not held")]
+    BoundNFTNotHeld,
+    // 53 - bound registry disagrees with the state or the owner store
+    #[msg("Bound registry mismatch")]
+    BoundRegistryMismatch,
+    // 54 - recorded owner of a token account differs from its live owner
+    #[msg("Balance out of sync")]
+    BalanceOutOfSync,
+    // 55 - account layout unknown to migrate
+    #[msg("Invalid migration")]
+    InvalidMigration,
+    // 56 - deposit of the bank not issued by the state
+    #[msg("Deposit not issued")]
+    DepositNotIssued,
+    // 57 - reveal can only be configured once
+    #[msg("Reveal already configured")]
+    RevealAlreadyConfigured,
+    // 58 - collection mint not created by `mint_collection` yet
+    #[msg("Collection not minted")]
+    CollectionNotMinted,
+    // 59 - a NFT must be worth some fungible tokens
+    #[msg("Invalid fungible supply")]
+    InvalidFungibleSupply,
+    // 60 - owner store lamports below the rent of its grown size
+    #[msg("Owner store underfunded")]
+    OwnerStoreUnderfunded,
+    // 61 - the randomness of a reroll is only known after its request slot
+    #[msg("Reroll not ready")]
+    RerollNotReady,
 }
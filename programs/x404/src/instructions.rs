@@ -1,6 +1,10 @@
 use crate::{
-    instruction, DepositParams, InitCollectionParams, InitTokenParams, IssueTokenParams,
-    RebalanceParams, RedeemParams, UnbindParams, ID,
+    instruction, AutoBindParams, BatchBindParams, BatchUnbindParams, DepositParams,
+    ExemptionParams, HookProgramParams, InitCollectionParams, InitTokenParams, IssueTokenParams,
+    LinkParams, MigrateParams, RebalanceParams, RedeemParams, RerollParams, RevealNFTParams,
+    RevealParams, RoyaltyParams, SetRerollParams, SetRevealParams, SettleRerollParams,
+    SkipNFTParams, SoulboundParams, SyncAutoBindParams, SyncBalanceParams, UnbindParams,
+    UpdateMetadataParams, ViewBankParams, ViewHolderParams, ViewOwnerParams, ViewStateParams, ID,
 };
 use anchor_lang::{prelude::*, system_program, InstructionData};
 use anchor_spl::{
@@ -22,7 +26,57 @@ pub fn initialize(state: Pubkey, signer: Pubkey) -> Instruction {
     )
 }
 
+pub fn add_hook_program(program: Pubkey, hub: Pubkey, signer: Pubkey) -> Instruction {
+    let data = instruction::AddHookProgram {
+        params: HookProgramParams { program },
+    };
+    Instruction::new_with_bytes(ID, &data.data(), update_hook_accounts(hub, signer))
+}
+
+pub fn remove_hook_program(program: Pubkey, hub: Pubkey, signer: Pubkey) -> Instruction {
+    let data = instruction::RemoveHookProgram {
+        params: HookProgramParams { program },
+    };
+    Instruction::new_with_bytes(ID, &data.data(), update_hook_accounts(hub, signer))
+}
+
+pub fn migrate(
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    fungible_mint: Pubkey,
+    banks: &[Pubkey],
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::Migrate {
+        params: MigrateParams { source },
+    };
+    let mut accounts = vec![
+        AccountMeta::new(hub, false),
+        AccountMeta::new(state, false),
+        AccountMeta::new(owner_store, false),
+        AccountMeta::new_readonly(fungible_mint, false),
+        AccountMeta::new(signer, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    accounts.extend(banks.iter().map(|bank| AccountMeta::new(*bank, false)));
+    Instruction::new_with_bytes(ID, &data.data(), accounts)
+}
+
+fn update_hook_accounts(hub: Pubkey, signer: Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(hub, false),
+        AccountMeta::new(signer, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ]
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn create_x404(
+    name: String,
+    symbol: String,
+    uri: String,
     redeem_max_deadline: u64,
     redeem_fee: u64,
     decimals: u8,
@@ -45,6 +99,9 @@ pub fn create_x404(
             fungible_supply,
             hook_extra_account,
             hook_program,
+            name,
+            symbol,
+            uri,
         },
     };
     Instruction::new_with_bytes(
@@ -57,6 +114,47 @@ pub fn create_x404(
             AccountMeta::new(owner_store, false),
             AccountMeta::new(collection_mint, false),
             AccountMeta::new(fungible_mint, false),
+            AccountMeta::new_readonly(hook_program, false),
+            AccountMeta::new(hook_extra_account, false),
+            AccountMeta::new(signer, true),
+            // token
+            AccountMeta::new_readonly(token_2022::ID, false),
+            // associated token
+            AccountMeta::new_readonly(associated_token::ID, false),
+            // x404
+            AccountMeta::new_readonly(ID, false),
+            // system
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_metadata(
+    name: String,
+    symbol: String,
+    uri: String,
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    fungible_mint: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::UpdateMetadata {
+        params: UpdateMetadataParams {
+            source,
+            name,
+            symbol,
+            uri,
+        },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(hub, false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new(fungible_mint, false),
             AccountMeta::new(signer, true),
             // token
             AccountMeta::new_readonly(token_2022::ID, false),
@@ -66,10 +164,12 @@ pub fn create_x404(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn mint_collection(
     name: String,
     symbol: String,
     uri: String,
+    max_size: u32,
     source: Pubkey,
     state: Pubkey,
     nft_mint: Pubkey,
@@ -77,10 +177,11 @@ pub fn mint_collection(
     signer: Pubkey,
 ) -> Instruction {
     let data = instruction::MintCollection {
-        _params: InitCollectionParams {
+        params: InitCollectionParams {
             name,
             symbol,
             uri,
+            max_size,
             source,
         },
     };
@@ -102,6 +203,7 @@ pub fn mint_collection(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn deposit_spl_nft(
     redeem_deadline: u64,
     source: Pubkey,
@@ -138,12 +240,18 @@ pub fn deposit_spl_nft(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn issue_token(
     source: Pubkey,
     state: Pubkey,
     owner_store: Pubkey,
+    exemption: Pubkey,
+    user_config: Pubkey,
+    collection_mint: Pubkey,
     nft_bank: Pubkey,
     nft_mint: Pubkey,
+    hook_program: Pubkey,
+    nft_meta_list: Pubkey,
     fungible_mint: Pubkey,
     fungible_token: Pubkey,
     user: Pubkey,
@@ -159,7 +267,12 @@ pub fn issue_token(
             AccountMeta::new(state, false),
             AccountMeta::new(nft_bank, false),
             AccountMeta::new(owner_store, false),
+            AccountMeta::new_readonly(exemption, false),
+            AccountMeta::new_readonly(user_config, false),
+            AccountMeta::new(collection_mint, false),
             AccountMeta::new(nft_mint, false),
+            AccountMeta::new_readonly(hook_program, false),
+            AccountMeta::new(nft_meta_list, false),
             AccountMeta::new(fungible_mint, false),
             AccountMeta::new(fungible_token, false),
             AccountMeta::new(user, true),
@@ -174,10 +287,14 @@ pub fn issue_token(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn redeem_spl_nft(
     source: Pubkey,
     state: Pubkey,
     owner_store: Pubkey,
+    exemption: Pubkey,
+    signer_config: Pubkey,
+    original_config: Pubkey,
     withdraw_mint: Pubkey,
     withdraw_holder: Pubkey,
     withdraw_receiver: Pubkey,
@@ -185,6 +302,7 @@ pub fn redeem_spl_nft(
     original_owner_account: Pubkey,
     fungible_mint: Pubkey,
     fungible_token: Pubkey,
+    linked_nft: Option<Pubkey>,
     signer: Pubkey,
 ) -> Instruction {
     let data = instruction::Redeem {
@@ -196,6 +314,9 @@ pub fn redeem_spl_nft(
         vec![
             AccountMeta::new(state, false),
             AccountMeta::new(owner_store, false),
+            AccountMeta::new_readonly(exemption, false),
+            AccountMeta::new_readonly(signer_config, false),
+            AccountMeta::new_readonly(original_config, false),
             AccountMeta::new(withdraw_mint, false),
             AccountMeta::new(withdraw_holder, false),
             AccountMeta::new(withdraw_receiver, false),
@@ -203,6 +324,8 @@ pub fn redeem_spl_nft(
             AccountMeta::new(fungible_mint, false),
             AccountMeta::new(fungible_token, false),
             AccountMeta::new(original_owner_account, false),
+            // the program id stands for no linked NFT
+            AccountMeta::new(linked_nft.unwrap_or(ID), false),
             AccountMeta::new(signer, true),
             // token
             AccountMeta::new_readonly(token::ID, false),
@@ -216,11 +339,311 @@ pub fn redeem_spl_nft(
     )
 }
 
+pub fn set_reveal(
+    placeholder_uri: String,
+    reveal_hash: [u8; 32],
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::SetReveal {
+        params: SetRevealParams {
+            source,
+            placeholder_uri,
+            reveal_hash,
+        },
+    };
+    Instruction::new_with_bytes(ID, &data.data(), reveal_accounts(hub, state, signer))
+}
+
+pub fn reveal(
+    base_uri: String,
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::Reveal {
+        params: RevealParams { source, base_uri },
+    };
+    Instruction::new_with_bytes(ID, &data.data(), reveal_accounts(hub, state, signer))
+}
+
+fn reveal_accounts(hub: Pubkey, state: Pubkey, signer: Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(hub, false),
+        AccountMeta::new(state, false),
+        AccountMeta::new_readonly(signer, true),
+    ]
+}
+
+pub fn set_auto_bind(
+    auto_bind: bool,
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::SetAutoBind {
+        params: AutoBindParams { source, auto_bind },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(hub, false),
+            AccountMeta::new(state, false),
+            AccountMeta::new_readonly(signer, true),
+        ],
+    )
+}
+
+// `nfts` are the (NFT mint, token account) pairs to reconcile
+pub fn sync_auto_bind(
+    nfts: &[(Pubkey, Pubkey)],
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+) -> Instruction {
+    let data = instruction::SyncAutoBind {
+        params: SyncAutoBindParams { source },
+    };
+    let mut accounts = vec![
+        AccountMeta::new_readonly(state, false),
+        AccountMeta::new_readonly(owner_store, false),
+        // token
+        AccountMeta::new_readonly(token_2022::ID, false),
+    ];
+    for (mint, token) in nfts {
+        accounts.push(AccountMeta::new(*mint, false));
+        accounts.push(AccountMeta::new(*token, false));
+    }
+    Instruction::new_with_bytes(ID, &data.data(), accounts)
+}
+
+pub fn set_soulbound(
+    soulbound: bool,
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::SetSoulbound {
+        params: SoulboundParams { source, soulbound },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(hub, false),
+            AccountMeta::new(state, false),
+            AccountMeta::new_readonly(signer, true),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn set_royalty(
+    royalty_bps: u16,
+    royalty_receiver: Pubkey,
+    marketplaces: Vec<Pubkey>,
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    collection_mint: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::SetRoyalty {
+        params: RoyaltyParams {
+            source,
+            royalty_bps,
+            royalty_receiver,
+            marketplaces,
+        },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(hub, false),
+            AccountMeta::new(state, false),
+            AccountMeta::new(collection_mint, false),
+            AccountMeta::new(signer, true),
+            AccountMeta::new_readonly(token_2022::ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+pub fn set_reroll(
+    reroll_fee: u64,
+    randomness: Pubkey,
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::SetReroll {
+        params: SetRerollParams {
+            source,
+            reroll_fee,
+            randomness,
+        },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(hub, false),
+            AccountMeta::new(state, false),
+            AccountMeta::new_readonly(signer, true),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn reroll(
+    nft_mint: Pubkey,
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    exemption: Pubkey,
+    signer_config: Pubkey,
+    owner_config: Pubkey,
+    fungible_mint: Pubkey,
+    fungible_token: Pubkey,
+    fee_receiver: Pubkey,
+    owner: Pubkey,
+    request: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::Reroll {
+        params: RerollParams { source, nft_mint },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new(owner_store, false),
+            AccountMeta::new_readonly(exemption, false),
+            AccountMeta::new_readonly(signer_config, false),
+            AccountMeta::new_readonly(owner_config, false),
+            AccountMeta::new(fungible_mint, false),
+            AccountMeta::new(fungible_token, false),
+            AccountMeta::new(fee_receiver, false),
+            AccountMeta::new_readonly(owner, false),
+            AccountMeta::new(request, false),
+            AccountMeta::new(signer, true),
+            // token
+            AccountMeta::new_readonly(token_2022::ID, false),
+            // ata
+            AccountMeta::new_readonly(associated_token::ID, false),
+            // system
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+pub fn settle_reroll(
+    holder: Pubkey,
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    request: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::SettleReroll {
+        params: SettleRerollParams { source, holder },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new(owner_store, false),
+            AccountMeta::new(request, false),
+            AccountMeta::new(holder, false),
+            AccountMeta::new_readonly(solana_program::sysvar::slot_hashes::ID, false),
+            AccountMeta::new_readonly(signer, true),
+        ],
+    )
+}
+
+pub fn reveal_nft(
+    number: u64,
+    path: String,
+    proof: Vec<[u8; 32]>,
+    source: Pubkey,
+    state: Pubkey,
+    nft_mint: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::RevealNft {
+        params: RevealNFTParams {
+            source,
+            number,
+            path,
+            proof,
+        },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new(nft_mint, false),
+            AccountMeta::new(signer, true),
+            // token
+            AccountMeta::new_readonly(token_2022::ID, false),
+            // system
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn link_deposit(
+    number: u64,
+    source: Pubkey,
+    state: Pubkey,
+    nft_bank: Pubkey,
+    deposit_mint: Pubkey,
+    deposit_receiver: Pubkey,
+    deposit_metadata: Pubkey,
+    nft_mint: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::LinkDeposit {
+        params: LinkParams { source, number },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new(nft_bank, false),
+            AccountMeta::new_readonly(deposit_mint, false),
+            AccountMeta::new_readonly(deposit_receiver, false),
+            AccountMeta::new_readonly(deposit_metadata, false),
+            AccountMeta::new(nft_mint, false),
+            AccountMeta::new(signer, true),
+            // token
+            AccountMeta::new_readonly(token_2022::ID, false),
+            // system
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn bind(
     number: u64,
     source: Pubkey,
     state: Pubkey,
     owner_store: Pubkey,
+    bound_registry: Pubkey,
     bind_mint: Pubkey,
     bind_receiver: Pubkey,
     fungible_mint: Pubkey,
@@ -228,7 +651,7 @@ pub fn bind(
     signer: Pubkey,
 ) -> Instruction {
     let data = instruction::BindNft {
-        _params: crate::BindParams { source, number },
+        params: crate::BindParams { source, number },
     };
     Instruction::new_with_bytes(
         ID,
@@ -236,6 +659,7 @@ pub fn bind(
         vec![
             AccountMeta::new(state, false),
             AccountMeta::new(owner_store, false),
+            AccountMeta::new(bound_registry, false),
             AccountMeta::new(bind_mint, false),
             AccountMeta::new(bind_receiver, false),
             AccountMeta::new(fungible_mint, false),
@@ -251,11 +675,15 @@ pub fn bind(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn unbind(
     number: u64,
     source: Pubkey,
     state: Pubkey,
     owner_store: Pubkey,
+    bound_registry: Pubkey,
+    exemption: Pubkey,
+    signer_config: Pubkey,
     unbind_mint: Pubkey,
     unbind_holder: Pubkey,
     fungible_mint: Pubkey,
@@ -271,6 +699,9 @@ pub fn unbind(
         vec![
             AccountMeta::new(state, false),
             AccountMeta::new(owner_store, false),
+            AccountMeta::new(bound_registry, false),
+            AccountMeta::new_readonly(exemption, false),
+            AccountMeta::new_readonly(signer_config, false),
             AccountMeta::new(unbind_mint, false),
             AccountMeta::new(unbind_holder, false),
             AccountMeta::new(fungible_mint, false),
@@ -286,9 +717,114 @@ pub fn unbind(
     )
 }
 
+// `nfts` are the (mint, token account of the signer) pairs of `numbers`
+#[allow(clippy::too_many_arguments)]
+pub fn batch_bind(
+    numbers: Vec<u64>,
+    nfts: &[(Pubkey, Pubkey)],
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    bound_registry: Pubkey,
+    fungible_mint: Pubkey,
+    fungible_token: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::BatchBindNft {
+        params: BatchBindParams { source, numbers },
+    };
+    let mut accounts = vec![
+        AccountMeta::new(state, false),
+        AccountMeta::new(owner_store, false),
+        AccountMeta::new(bound_registry, false),
+        AccountMeta::new(fungible_mint, false),
+        AccountMeta::new(fungible_token, false),
+        AccountMeta::new(signer, true),
+        // token
+        AccountMeta::new_readonly(token_2022::ID, false),
+        // ata
+        AccountMeta::new_readonly(associated_token::ID, false),
+        // system
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    for (mint, token) in nfts {
+        accounts.push(AccountMeta::new(*mint, false));
+        accounts.push(AccountMeta::new(*token, false));
+    }
+    Instruction::new_with_bytes(ID, &data.data(), accounts)
+}
+
+// `nfts` are the (mint, token account of the signer) pairs of `numbers`
+#[allow(clippy::too_many_arguments)]
+pub fn batch_unbind(
+    numbers: Vec<u64>,
+    nfts: &[(Pubkey, Pubkey)],
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    bound_registry: Pubkey,
+    exemption: Pubkey,
+    signer_config: Pubkey,
+    fungible_mint: Pubkey,
+    fungible_token: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::BatchUnbindNft {
+        params: BatchUnbindParams { source, numbers },
+    };
+    let mut accounts = vec![
+        AccountMeta::new(state, false),
+        AccountMeta::new(owner_store, false),
+        AccountMeta::new(bound_registry, false),
+        AccountMeta::new_readonly(exemption, false),
+        AccountMeta::new_readonly(signer_config, false),
+        AccountMeta::new(fungible_mint, false),
+        AccountMeta::new(fungible_token, false),
+        AccountMeta::new(signer, true),
+        // token
+        AccountMeta::new_readonly(token_2022::ID, false),
+        // ata
+        AccountMeta::new_readonly(associated_token::ID, false),
+        // system
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    for (mint, token) in nfts {
+        accounts.push(AccountMeta::new(*mint, false));
+        accounts.push(AccountMeta::new(*token, false));
+    }
+    Instruction::new_with_bytes(ID, &data.data(), accounts)
+}
+
+pub fn transfer_bound(
+    state: Pubkey,
+    bound_registry: Pubkey,
+    nft_mint: Pubkey,
+    source_token: Pubkey,
+    destination_token: Pubkey,
+    hook: Pubkey,
+) -> Instruction {
+    let data = instruction::TransferBound {};
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new(bound_registry, false),
+            AccountMeta::new_readonly(nft_mint, false),
+            AccountMeta::new_readonly(source_token, false),
+            AccountMeta::new_readonly(destination_token, false),
+            AccountMeta::new_readonly(hook, true),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn rebalance(
     state: Pubkey,
     owner_store: Pubkey,
+    exemption: Pubkey,
+    sender_config: Pubkey,
+    receiver_config: Pubkey,
     sender: Pubkey,
     receiver: Pubkey,
     amount: u64,
@@ -310,6 +846,9 @@ pub fn rebalance(
         vec![
             AccountMeta::new_readonly(state, false),
             AccountMeta::new(owner_store, false),
+            AccountMeta::new_readonly(exemption, false),
+            AccountMeta::new_readonly(sender_config, false),
+            AccountMeta::new_readonly(receiver_config, false),
             AccountMeta::new_readonly(fungible_mint, false),
             AccountMeta::new_readonly(sender_token, false),
             AccountMeta::new_readonly(receiver_token, false),
@@ -321,3 +860,206 @@ pub fn rebalance(
         ],
     )
 }
+
+#[allow(clippy::too_many_arguments)]
+pub fn add_exemption(
+    owner: Pubkey,
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    exemption: Pubkey,
+    holder_config: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::AddExemption {
+        params: ExemptionParams { source, owner },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        update_exemption_accounts(hub, state, owner_store, exemption, holder_config, signer),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn remove_exemption(
+    owner: Pubkey,
+    source: Pubkey,
+    hub: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    exemption: Pubkey,
+    holder_config: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::RemoveExemption {
+        params: ExemptionParams { source, owner },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        update_exemption_accounts(hub, state, owner_store, exemption, holder_config, signer),
+    )
+}
+
+fn update_exemption_accounts(
+    hub: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    exemption: Pubkey,
+    holder_config: Pubkey,
+    signer: Pubkey,
+) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(hub, false),
+        AccountMeta::new_readonly(state, false),
+        AccountMeta::new(owner_store, false),
+        AccountMeta::new(exemption, false),
+        AccountMeta::new_readonly(holder_config, false),
+        AccountMeta::new(signer, true),
+        // system
+        AccountMeta::new_readonly(system_program::ID, false),
+    ]
+}
+
+pub fn set_skip_nft(
+    skip: bool,
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    exemption: Pubkey,
+    holder_config: Pubkey,
+    signer: Pubkey,
+) -> Instruction {
+    let data = instruction::SetSkipNft {
+        params: SkipNFTParams { source, skip },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new(owner_store, false),
+            AccountMeta::new_readonly(exemption, false),
+            AccountMeta::new(holder_config, false),
+            AccountMeta::new(signer, true),
+            // system
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn sync_balance(
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    exemption: Pubkey,
+    holder_config: Pubkey,
+    fungible_mint: Pubkey,
+    token_account: Pubkey,
+    payer: Pubkey,
+) -> Instruction {
+    let data = instruction::SyncBalance {
+        _params: SyncBalanceParams { source },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new(owner_store, false),
+            AccountMeta::new_readonly(exemption, false),
+            AccountMeta::new_readonly(holder_config, false),
+            AccountMeta::new_readonly(fungible_mint, false),
+            AccountMeta::new_readonly(token_account, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
+
+pub fn get_owner_nfts(
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    owner: Pubkey,
+) -> Instruction {
+    let data = instruction::GetOwnerNfts {
+        params: ViewOwnerParams { source, owner },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(owner_store, false),
+        ],
+    )
+}
+
+pub fn get_nft_holder(
+    number: u64,
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    nft_mint: Pubkey,
+) -> Instruction {
+    let data = instruction::GetNftHolder {
+        _params: ViewHolderParams { source, number },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(owner_store, false),
+            AccountMeta::new_readonly(nft_mint, false),
+        ],
+    )
+}
+
+pub fn audit_state(
+    source: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    bound_registry: Pubkey,
+    fungible_mint: Pubkey,
+) -> Instruction {
+    let data = instruction::AuditState {
+        _params: ViewStateParams { source },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(owner_store, false),
+            AccountMeta::new_readonly(bound_registry, false),
+            AccountMeta::new_readonly(fungible_mint, false),
+        ],
+    )
+}
+
+pub fn get_state_summary(source: Pubkey, state: Pubkey) -> Instruction {
+    let data = instruction::GetStateSummary {
+        _params: ViewStateParams { source },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![AccountMeta::new_readonly(state, false)],
+    )
+}
+
+pub fn get_bank_info(id: Pubkey, nft_bank: Pubkey) -> Instruction {
+    let data = instruction::GetBankInfo {
+        _params: ViewBankParams { id },
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![AccountMeta::new_readonly(nft_bank, false)],
+    )
+}
@@ -12,11 +12,19 @@ declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 mod x404 {
     use std::{borrow::BorrowMut, collections::HashMap, ops::Deref};
 
-    use anchor_spl::token_2022::spl_token_2022::{
-        extension::{transfer_hook::instruction::initialize as hook_initialize, ExtensionType},
+    use anchor_lang::Discriminator;
+    use anchor_spl::associated_token::{create as create_associated_token_account, Create};
+    use anchor_spl::token_interface::TokenAccount;
+    use solana_program::program::{invoke, set_return_data};
+    use spl_token_2022::{
+        extension::{
+            metadata_pointer::instruction::initialize as metadata_pointer_initialize,
+            transfer_hook::instruction::initialize as hook_initialize, BaseStateWithExtensions,
+            ExtensionType, StateWithExtensions,
+        },
         state::Mint,
     };
-    use solana_program::program::invoke;
+    use spl_token_metadata_interface::state::{Field, TokenMetadata};
     use utils::transfer_from_owner_store;
 
     use super::*;
@@ -26,6 +34,7 @@ mod x404 {
         let hub = &mut ctx.accounts.state;
         hub.manager = *ctx.accounts.signer.key;
         hub.emergency_close = false;
+        hub.hook_programs = Vec::new();
         msg!(
             "Initialized new hub: {} with owner {}!",
             hub.to_account_info().key,
@@ -34,6 +43,133 @@ mod x404 {
         Ok(())
     }
 
+    pub fn add_hook_program(
+        ctx: Context<UpdateHookPrograms>,
+        params: HookProgramParams,
+    ) -> Result<()> {
+        msg!("check permission for add hook program");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
+
+        if ctx.accounts.hub.hook_programs.contains(&params.program) {
+            msg!("{} already approved", params.program);
+            return Ok(());
+        }
+
+        ctx.accounts.hub.hook_programs.push(params.program);
+        resize_account(
+            ctx.accounts.hub.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.hub.space(),
+        )?;
+
+        msg!("{} approved", params.program);
+        Ok(())
+    }
+
+    pub fn remove_hook_program(
+        ctx: Context<UpdateHookPrograms>,
+        params: HookProgramParams,
+    ) -> Result<()> {
+        msg!("check permission for remove hook program");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
+
+        if !ctx.accounts.hub.hook_programs.contains(&params.program) {
+            msg!("{} not approved", params.program);
+            return Ok(());
+        }
+
+        // existing states keep their hook, only new states are affected
+        ctx.accounts
+            .hub
+            .hook_programs
+            .retain(|program| program != &params.program);
+        resize_account(
+            ctx.accounts.hub.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.hub.space(),
+        )?;
+
+        msg!("{} revoked", params.program);
+        Ok(())
+    }
+
+    pub fn migrate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Migrate<'info>>,
+        params: MigrateParams,
+    ) -> Result<()> {
+        let hub = ctx.accounts.hub.to_account_info();
+        let payer = ctx.accounts.signer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+
+        msg!("check permission for migrate");
+        if hub.try_borrow_data()?[..8] != X404Hub::DISCRIMINATOR {
+            return err!(SolX404Error::InvalidMigration);
+        }
+        // the manager sits at the same offset in every hub layout
+        let manager = Pubkey::try_from(&hub.try_borrow_data()?[9..41]).unwrap();
+        require_keys_eq!(
+            ctx.accounts.signer.key(),
+            manager,
+            SolX404Error::OnlyCallByOwner
+        );
+
+        if grow_legacy_account(
+            hub.clone(),
+            payer.clone(),
+            system_program.clone(),
+            X404Hub::DISCRIMINATOR,
+            LEGACY_HUB_SIZE,
+            LEGACY_HUB_SIZE + 4,
+        )? {
+            msg!("migrated hub");
+        }
+
+        if migrate_state_account(
+            ctx.accounts.state.to_account_info(),
+            &ctx.accounts.fungible_mint.to_account_info(),
+            payer.clone(),
+            system_program.clone(),
+        )? {
+            msg!("migrated state {}", ctx.accounts.state.key());
+        }
+
+        if migrate_owner_store_account(
+            ctx.accounts.owner_store.to_account_info(),
+            params.source,
+            payer.clone(),
+            system_program.clone(),
+        )? {
+            msg!("migrated owner store {}", ctx.accounts.owner_store.key());
+        }
+
+        // remaining accounts: NFT banks created before the linked NFT was
+        // recorded
+        for bank in ctx.remaining_accounts.iter() {
+            if grow_legacy_account(
+                bank.clone(),
+                payer.clone(),
+                system_program.clone(),
+                NFTBank::DISCRIMINATOR,
+                LEGACY_BANK_SIZE,
+                BANK_SIZE,
+            )? {
+                msg!("migrated bank {}", bank.key());
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn create_x404(ctx: Context<CreateX404>, params: InitTokenParams) -> Result<()> {
         msg!("check permission for create x404");
         require_eq!(
@@ -41,9 +177,38 @@ mod x404 {
             ctx.accounts.hub.manager,
             SolX404Error::OnlyCallByOwner
         );
+        // every NFT count divides balances by the fungible supply
+        require_gt!(
+            params.fungible_supply,
+            0,
+            SolX404Error::InvalidFungibleSupply
+        );
+
+        // `fungible_hook` is the only signer allowed to call `rebalance`
+        require!(
+            ctx.accounts
+                .hub
+                .hook_programs
+                .contains(&params.hook_program),
+            SolX404Error::HookProgramNotApproved
+        );
+        // and must be the meta list PDA of the fungible mint under that program
+        let (extra_account, _) = Pubkey::find_program_address(
+            &[
+                b"extra-account-metas",
+                ctx.accounts.fungible_mint.key.as_ref(),
+            ],
+            &params.hook_program,
+        );
+        require_keys_eq!(
+            params.hook_extra_account,
+            extra_account,
+            SolX404Error::InvalidHookExtraAccount
+        );
 
         msg!("initialize x404 state");
         let state = &mut ctx.accounts.state;
+        state.version = X404_STATE_VERSION;
         state.source = ctx.accounts.source.to_account_info().key();
         state.decimal = params.decimals;
         state.redeem_fee = params.redeem_fee;
@@ -52,9 +217,20 @@ mod x404 {
         state.fungible_mint = ctx.accounts.fungible_mint.to_account_info().key();
         state.collection_mint = ctx.accounts.collection_mint.to_account_info().key();
         state.fungible_hook = params.hook_extra_account;
+        state.hook_program = params.hook_program;
+        state.royalty_bps = 0;
+        state.royalty_receiver = ctx.accounts.signer.key();
+        state.marketplaces = Vec::new();
+        state.soulbound = false;
+        state.auto_bind = false;
         state.nft_supply = 0;
         state.nft_in_use = 0;
         state.fungible_supply = params.fungible_supply;
+        state.placeholder_uri = String::new();
+        state.reveal_hash = [0; 32];
+        state.base_uri = String::new();
+        state.reroll_fee = 0;
+        state.randomness = Pubkey::default();
 
         msg!("create fungible mint");
 
@@ -64,8 +240,10 @@ mod x404 {
             &[ctx.bumps.fungible_mint],
         ];
 
-        let mint_size =
-            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook])?;
+        let mint_size = ExtensionType::try_calculate_account_len::<Mint>(&[
+            ExtensionType::TransferHook,
+            ExtensionType::MetadataPointer,
+        ])?;
 
         create_new_account(
             seeds.as_ref(),
@@ -88,6 +266,20 @@ mod x404 {
 
         invoke(&extra_init, &[ctx.accounts.fungible_mint.to_account_info()])?;
 
+        msg!("initialize metadata pointer");
+        // the metadata lives in the mint itself
+        let pointer_init = metadata_pointer_initialize(
+            ctx.accounts.token_program.key,
+            ctx.accounts.fungible_mint.key,
+            Some(ctx.accounts.state.key()),
+            Some(ctx.accounts.fungible_mint.key()),
+        )?;
+
+        invoke(
+            &pointer_init,
+            &[ctx.accounts.fungible_mint.to_account_info()],
+        )?;
+
         // init fungible mint
 
         msg!("initiate fungible mint");
@@ -96,13 +288,119 @@ mod x404 {
             ctx.accounts.fungible_mint.to_account_info(),
             ctx.accounts.state.to_account_info(),
             params.decimals,
+            None,
+        )?;
+
+        msg!("initialize fungible metadata");
+        let metadata = TokenMetadata {
+            update_authority: Some(ctx.accounts.state.key()).try_into()?,
+            mint: ctx.accounts.fungible_mint.key(),
+            name: params.name.clone(),
+            symbol: params.symbol.clone(),
+            uri: params.uri.clone(),
+            additional_metadata: vec![],
+        };
+        // token2022 reallocs the mint for the metadata, fund it beforehand
+        top_up_rent(
+            ctx.accounts.fungible_mint.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            mint_size + metadata.tlv_size_of()?,
+        )?;
+
+        let source = ctx.accounts.source.key();
+        let state_seeds = [b"state", source.as_ref(), &[ctx.bumps.state]];
+        initialize_token_metadata(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.fungible_mint.to_account_info(),
+            ctx.accounts.state.to_account_info(),
+            params.name,
+            params.symbol,
+            params.uri,
+            &[state_seeds.as_ref()],
         )?;
 
         msg!("fungible mint created successfully");
 
+        msg!("initialize hook extra account meta list");
+        initialize_hook_meta_list(
+            ctx.accounts.hook_program.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.hook_extra_account.to_account_info(),
+            ctx.accounts.fungible_mint.to_account_info(),
+            ctx.accounts.associated_token_program.to_account_info(),
+            ctx.accounts.x404_program.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        ctx.accounts.owner_store.source = ctx.accounts.source.key();
+        ctx.accounts.owner_store.version = OWNER_STORE_VERSION;
         let initiate_map = HashMap::<Pubkey, Vec<Pubkey>>::new();
         ctx.accounts.owner_store.store = initiate_map.try_to_vec()?;
+        let initiate_index = HashMap::<Pubkey, Pubkey>::new();
+        ctx.accounts.owner_store.index = initiate_index.try_to_vec()?;
+        let initiate_balances = HashMap::<Pubkey, AccountBalance>::new();
+        ctx.accounts.owner_store.balances = initiate_balances.try_to_vec()?;
+
+        Ok(())
+    }
+
+    pub fn update_metadata(
+        ctx: Context<UpdateFungibleMetadata>,
+        params: UpdateMetadataParams,
+    ) -> Result<()> {
+        msg!("check permission for update metadata");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
+
+        let mint_info = ctx.accounts.fungible_mint.to_account_info();
+        let (current_size, new_size) = {
+            let mint_data = mint_info.try_borrow_data()?;
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+            let mut metadata = mint.get_variable_len_extension::<TokenMetadata>()?;
+            let current_size = metadata.tlv_size_of()?;
+            metadata.update(Field::Name, params.name.clone());
+            metadata.update(Field::Symbol, params.symbol.clone());
+            metadata.update(Field::Uri, params.uri.clone());
+            (
+                mint_data.len(),
+                mint_data.len() - current_size + metadata.tlv_size_of()?,
+            )
+        };
+
+        // token2022 reallocs the mint when a field grows
+        top_up_rent(
+            mint_info.clone(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            new_size.max(current_size),
+        )?;
+
+        let state_seeds = [
+            b"state",
+            ctx.accounts.state.source.as_ref(),
+            &[ctx.bumps.state],
+        ];
+        for (field, value) in [
+            (Field::Name, params.name),
+            (Field::Symbol, params.symbol),
+            (Field::Uri, params.uri),
+        ] {
+            update_token_metadata(
+                ctx.accounts.token_program.to_account_info(),
+                mint_info.clone(),
+                ctx.accounts.state.to_account_info(),
+                field,
+                value,
+                &[state_seeds.as_ref()],
+            )?;
+        }
 
+        msg!("metadata of {} updated", mint_info.key());
         Ok(())
     }
 
@@ -112,7 +410,7 @@ mod x404 {
     // here to make sure not initiate state twice.
     pub fn mint_collection(
         ctx: Context<MintCollection>,
-        _params: InitCollectionParams,
+        params: InitCollectionParams,
     ) -> Result<()> {
         msg!("check permission for create collection");
 
@@ -128,7 +426,19 @@ mod x404 {
             SolX404Error::InvalidNFTAddress
         );
 
-        msg!("start to mint");
+        require!(
+            ctx.accounts.collection_mint.data_is_empty(),
+            SolX404Error::NFTAlreadyMinted
+        );
+
+        msg!("create collection mint");
+        let state_key = ctx.accounts.state.key();
+        let mint_seeds = [
+            b"collection_mint",
+            state_key.as_ref(),
+            &[ctx.bumps.collection_mint],
+        ];
+
         // seeds for state account
         let seeds = [
             b"state",
@@ -137,14 +447,55 @@ mod x404 {
         ];
 
         let state_signer = [seeds.as_ref()];
-        mint_nft(
+        create_nft_mint(
             ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.collection_mint.borrow_mut(),
-            ctx.accounts.collection_token.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.collection_mint.to_account_info(),
             ctx.accounts.state.to_account_info(),
+            mint_seeds.as_ref(),
             state_signer.as_slice(),
+            TokenMetadata {
+                update_authority: Some(state_key).try_into()?,
+                mint: ctx.accounts.collection_mint.key(),
+                name: params.name,
+                symbol: params.symbol,
+                uri: params.uri,
+                additional_metadata: vec![],
+            },
+            None,
+            false,
+            Some(NftGroup::Collection {
+                max_size: params.max_size,
+            }),
         )?;
 
+        msg!("start to mint");
+        create_associated_token_account(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            Create {
+                payer: ctx.accounts.signer.to_account_info(),
+                associated_token: ctx.accounts.collection_token.to_account_info(),
+                authority: ctx.accounts.collection_mint.to_account_info(),
+                mint: ctx.accounts.collection_mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
+
+        require!(
+            mint_token(
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.collection_mint.to_account_info(),
+                ctx.accounts.collection_token.to_account_info(),
+                1,
+                ctx.accounts.state.to_account_info(),
+                state_signer.as_slice(),
+            )
+            .is_ok(),
+            SolX404Error::FailedToMintNFT
+        );
+
         msg!("NFT minted successfully.");
         Ok(())
     }
@@ -185,6 +536,7 @@ mod x404 {
         ctx.accounts.nft_bank.owner = ctx.accounts.signer.to_account_info().key();
         ctx.accounts.nft_bank.redeem_deadline = params.redeem_deadline + Clock::get()?.epoch;
         ctx.accounts.nft_bank.issued = false;
+        ctx.accounts.nft_bank.linked_nft = Pubkey::default();
         Ok(())
     }
 
@@ -222,41 +574,122 @@ mod x404 {
 
         msg!("Fungible Token minted successfully.");
 
+        let mut store = ctx.accounts.owner_store.load();
+        increase_balance(
+            &mut store,
+            ctx.accounts.fungible_token.key(),
+            ctx.accounts.user.key(),
+            ctx.accounts.state.fungible_supply,
+        )?;
+
+        let exemptions = load_exemptions(&ctx.accounts.exemption)?;
+        let holder = nft_holder(
+            &exemptions,
+            &ctx.accounts.user_config,
+            ctx.accounts.user.key(),
+            ctx.accounts.state.key(),
+        )?;
+
         if ctx.accounts.state.nft_supply > ctx.accounts.state.nft_in_use {
             msg!("use existed nft");
-            transfer_from_owner_store(
-                &mut ctx.accounts.owner_store,
-                ctx.accounts.state.key(),
-                ctx.accounts.user.key(),
-                1,
-            )?;
-            ctx.accounts.state.nft_in_use += 1;
+            transfer_from_owner_store(&mut store, ctx.accounts.state.key(), holder, 1)?;
         } else {
             // if the mint is initiated before, then it never added to the store
             // otherwise, it should be added to the store now
+            let number = ctx.accounts.state.nft_supply;
+            let state_key = ctx.accounts.state.key();
+            let number_bytes = number.to_le_bytes();
+            let mint_seeds = [
+                b"nft_mint",
+                state_key.as_ref(),
+                number_bytes.as_ref(),
+                &[ctx.bumps.nft_mint],
+            ];
+
+            msg!("create nft mint #{}", number);
+            let collection = {
+                let collection_data = ctx.accounts.collection_mint.try_borrow_data()?;
+                let collection = StateWithExtensions::<Mint>::unpack(&collection_data)?;
+                collection.get_variable_len_extension::<TokenMetadata>()?
+            };
+            create_nft_mint(
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.nft_mint.to_account_info(),
+                ctx.accounts.state.to_account_info(),
+                mint_seeds.as_ref(),
+                [state_seeds.as_ref()].as_slice(),
+                TokenMetadata {
+                    update_authority: Some(state_key).try_into()?,
+                    mint: ctx.accounts.nft_mint.key(),
+                    name: format!("{} #{}", collection.symbol, number),
+                    symbol: collection.symbol,
+                    uri: if ctx.accounts.state.delayed_reveal() {
+                        ctx.accounts.state.placeholder_uri.clone()
+                    } else {
+                        format!("{}/{}.json", collection.uri.trim_end_matches('/'), number)
+                    },
+                    additional_metadata: vec![],
+                },
+                Some(ctx.accounts.hook_program.key()),
+                ctx.accounts.state.auto_bind,
+                Some(NftGroup::Member {
+                    group: ctx.accounts.collection_mint.to_account_info(),
+                }),
+            )?;
+            initialize_nft_meta_list(
+                ctx.accounts.hook_program.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.nft_meta_list.to_account_info(),
+                ctx.accounts.nft_mint.to_account_info(),
+                ctx.accounts.state.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                [state_seeds.as_ref()].as_slice(),
+            )?;
 
-            ctx.accounts.state.nft_supply += 1;
+            ctx.accounts.state.nft_supply = ctx
+                .accounts
+                .state
+                .nft_supply
+                .checked_add(1)
+                .ok_or(SolX404Error::ArithmeticOverflow)?;
 
             msg!("update owner store");
-            add_to_owner_store(
-                &mut ctx.accounts.owner_store,
-                ctx.accounts.nft_mint.key(),
-                ctx.accounts.user.key(),
-            )?;
+            add_to_owner_store(&mut store, ctx.accounts.nft_mint.key(), holder)?;
 
             msg!("NFT minted successfully.");
         }
 
-        ctx.accounts.state.nft_in_use += 1;
+        ctx.accounts.state.nft_in_use = ctx
+            .accounts
+            .state
+            .nft_in_use
+            .checked_add(1)
+            .ok_or(SolX404Error::ArithmeticOverflow)?;
         ctx.accounts.nft_bank.issued = true;
         msg!("NFT recorded");
 
-        Ok(())
+        save_owner_store(
+            &mut ctx.accounts.owner_store,
+            &store,
+            Some((
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
+        )
     }
 
     pub fn redeem(ctx: Context<RedeemSPLNFT>, _params: RedeemParams) -> Result<()> {
         msg!("check permission for redeem NFT");
 
+        let charge = ctx
+            .accounts
+            .state
+            .redeem_fee
+            .checked_add(ctx.accounts.state.fungible_supply)
+            .ok_or(SolX404Error::ArithmeticOverflow)?;
+
         // redeem check
         if ctx.accounts.signer.key() != ctx.accounts.nft_bank.owner {
             require_gt!(
@@ -267,7 +700,7 @@ mod x404 {
 
             require_gte!(
                 ctx.accounts.fungible_token.amount,
-                ctx.accounts.state.redeem_fee + ctx.accounts.state.fungible_supply,
+                charge,
                 SolX404Error::InsufficientFee
             );
         } else {
@@ -286,20 +719,36 @@ mod x404 {
 
         let state_signer = [seeds.as_ref()];
 
+        let mut store = ctx.accounts.owner_store.load();
         if ctx.accounts.signer.key() != ctx.accounts.nft_bank.owner {
             // charge fee
+            let sender_before = decrease_balance(
+                &mut store,
+                ctx.accounts.fungible_token.key(),
+                ctx.accounts.signer.key(),
+                charge,
+            )?;
+            let receiver_before = increase_balance(
+                &mut store,
+                ctx.accounts.original_owner.key(),
+                ctx.accounts.nft_bank.owner,
+                ctx.accounts.state.redeem_fee,
+            )?;
 
-            let to_remove = (ctx.accounts.fungible_token.amount
-                / ctx.accounts.state.fungible_supply) as usize
-                - ((ctx.accounts.fungible_token.amount
-                    - ctx.accounts.state.redeem_fee
-                    - ctx.accounts.state.fungible_supply)
-                    / ctx.accounts.state.fungible_supply) as usize;
-
-            let to_add = ((ctx.accounts.original_owner.amount + ctx.accounts.state.redeem_fee)
-                / ctx.accounts.state.fungible_supply) as usize
-                - (ctx.accounts.original_owner.amount / ctx.accounts.state.fungible_supply)
-                    as usize;
+            let to_remove = nft_delta(
+                sender_before,
+                sender_before
+                    .checked_sub(charge)
+                    .ok_or(SolX404Error::ArithmeticUnderflow)?,
+                ctx.accounts.state.fungible_supply,
+            )?;
+            let to_add = nft_delta(
+                receiver_before,
+                receiver_before
+                    .checked_add(ctx.accounts.state.redeem_fee)
+                    .ok_or(SolX404Error::ArithmeticOverflow)?,
+                ctx.accounts.state.fungible_supply,
+            )?;
 
             // we do not want to trigger the hook here
             // so we use mint and burn way
@@ -308,7 +757,7 @@ mod x404 {
                 ctx.accounts.token_program.to_account_info(),
                 ctx.accounts.fungible_mint.to_account_info(),
                 ctx.accounts.fungible_token.to_account_info(),
-                ctx.accounts.state.redeem_fee + ctx.accounts.state.fungible_supply,
+                charge,
                 ctx.accounts.signer.to_account_info(),
             )?;
 
@@ -333,17 +782,52 @@ mod x404 {
                 to_add
             );
 
+            let exemptions = load_exemptions(&ctx.accounts.exemption)?;
+            let sender = ctx.accounts.fungible_token.deref().owner;
+            let receiver = ctx.accounts.original_owner.deref().owner;
+            let sender_holder = nft_holder(
+                &exemptions,
+                &ctx.accounts.signer_config,
+                sender,
+                ctx.accounts.state.key(),
+            )?;
+            let receiver_holder = nft_holder(
+                &exemptions,
+                &ctx.accounts.original_config,
+                receiver,
+                ctx.accounts.state.key(),
+            )?;
             do_rebalance(
-                &mut ctx.accounts.owner_store,
-                ctx.accounts.fungible_token.deref().owner,
-                ctx.accounts.original_owner.deref().owner,
+                &mut store,
+                sender_holder,
+                receiver_holder,
                 ctx.accounts.state.to_account_info().key(),
                 to_add,
                 to_remove,
             )?;
 
+            check_nft_balance(
+                &store,
+                sender,
+                sender_holder,
+                ctx.accounts.state.fungible_supply,
+            )?;
+            check_nft_balance(
+                &store,
+                receiver,
+                receiver_holder,
+                ctx.accounts.state.fungible_supply,
+            )?;
+
             msg!("redeem fee charged.");
         } else {
+            decrease_balance(
+                &mut store,
+                ctx.accounts.fungible_token.key(),
+                ctx.accounts.signer.key(),
+                ctx.accounts.state.fungible_supply,
+            )?;
+
             burn_token(
                 ctx.accounts.token_program.to_account_info(),
                 ctx.accounts.fungible_mint.to_account_info(),
@@ -353,11 +837,20 @@ mod x404 {
             )?;
             msg!("{} lose {}", ctx.accounts.fungible_token.deref().owner, 1);
             // remove nft due to redeem. Must be 1.
-            transfer_from_owner_store(
-                &mut ctx.accounts.owner_store,
+            let exemptions = load_exemptions(&ctx.accounts.exemption)?;
+            let holder = nft_holder(
+                &exemptions,
+                &ctx.accounts.signer_config,
                 ctx.accounts.signer.key(),
                 ctx.accounts.state.key(),
-                1,
+            )?;
+            transfer_from_owner_store(&mut store, holder, ctx.accounts.state.key(), 1)?;
+
+            check_nft_balance(
+                &store,
+                ctx.accounts.signer.key(),
+                holder,
+                ctx.accounts.state.fungible_supply,
             )?;
         }
 
@@ -382,56 +875,541 @@ mod x404 {
             &state_signer,
         )?;
 
-        ctx.accounts.state.nft_in_use -= 1;
+        ctx.accounts.state.nft_in_use = ctx
+            .accounts
+            .state
+            .nft_in_use
+            .checked_sub(1)
+            .ok_or(SolX404Error::ArithmeticUnderflow)?;
+
+        // the deposit leaves, the issued NFT mirroring it gets its own metadata back
+        let source = ctx.accounts.state.source;
+        if ctx.accounts.nft_bank.linked_nft != Pubkey::default() {
+            let linked_nft = ctx
+                .accounts
+                .linked_nft
+                .as_ref()
+                .ok_or(SolX404Error::InvalidLinkedNFT)?;
+            require_keys_eq!(
+                linked_nft.key(),
+                ctx.accounts.nft_bank.linked_nft,
+                SolX404Error::InvalidLinkedNFT
+            );
+
+            unlink_metadata(
+                ctx.accounts.token_program.to_account_info(),
+                linked_nft.to_account_info(),
+                ctx.accounts.state.to_account_info(),
+                &[&[b"state", source.as_ref(), &[ctx.bumps.state]]],
+            )?;
+            ctx.accounts.nft_bank.linked_nft = Pubkey::default();
+            msg!("{} unlinked", linked_nft.key());
+        }
+
+        save_owner_store(
+            &mut ctx.accounts.owner_store,
+            &store,
+            Some((
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
+        )
+    }
+
+    pub fn set_reveal(ctx: Context<SetReveal>, params: SetRevealParams) -> Result<()> {
+        msg!("check permission for set reveal");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
+        let state = &mut ctx.accounts.state;
+        // NFTs issued since keep the committed placeholder and paths
+        require!(
+            !state.delayed_reveal(),
+            SolX404Error::RevealAlreadyConfigured
+        );
+        require!(!state.revealed(), SolX404Error::AlreadyRevealed);
+        require_gte!(
+            MAX_URI_LENGTH,
+            params.placeholder_uri.len(),
+            SolX404Error::UriTooLong
+        );
 
+        state.placeholder_uri = params.placeholder_uri;
+        state.reveal_hash = params.reveal_hash;
         Ok(())
     }
 
-    pub fn bind_nft(ctx: Context<BindNFT>, _params: BindParams) -> Result<()> {
-        msg!("check permission for bind nft");
+    pub fn reveal(ctx: Context<Reveal>, params: RevealParams) -> Result<()> {
+        msg!("check permission for reveal");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
+        let state = &mut ctx.accounts.state;
+        require!(state.delayed_reveal(), SolX404Error::RevealNotConfigured);
+        require!(!state.revealed(), SolX404Error::AlreadyRevealed);
+        require_gte!(
+            MAX_URI_LENGTH,
+            params.base_uri.len(),
+            SolX404Error::UriTooLong
+        );
 
-        // fetch the nft
+        state.base_uri = params.base_uri.trim_end_matches('/').to_string();
+        msg!("revealed at {}", state.base_uri);
+        Ok(())
+    }
 
-        take_from_owner_store(
-            &mut ctx.accounts.owner_store,
+    pub fn set_soulbound(ctx: Context<SetSoulbound>, params: SoulboundParams) -> Result<()> {
+        msg!("check permission for set soulbound");
+        require_eq!(
             ctx.accounts.signer.key(),
-            ctx.accounts.bind_mint.key(),
-        )?;
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
 
-        // burn the token for bind
+        ctx.accounts.state.soulbound = params.soulbound;
+        Ok(())
+    }
 
-        burn_token(
-            ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.fungible_mint.to_account_info(),
-            ctx.accounts.fungible_token.to_account_info(),
-            ctx.accounts.state.fungible_supply,
-            ctx.accounts.signer.to_account_info(),
-        )?;
+    pub fn set_auto_bind(ctx: Context<SetAutoBind>, params: AutoBindParams) -> Result<()> {
+        msg!("check permission for set auto bind");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
 
-        // send the corresponding nft to the signer
-        let seeds = [
-            b"state",
-            ctx.accounts.state.source.as_ref(),
-            &[ctx.bumps.state],
-        ];
+        ctx.accounts.state.auto_bind = params.auto_bind;
+        Ok(())
+    }
 
+    // reconcile the NFT copies in holder wallets with the owner store.
+    // `rebalance` runs under the token program through the hook and the
+    // runtime rejects a CPI back into a program already on the stack, so it
+    // cannot mint or burn and keeps the assignment virtual. Placed after the
+    // transfer in the same transaction, this moves the copies atomically with
+    // the tokens. Copies are only burned once the mode is off.
+    pub fn sync_auto_bind<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SyncAutoBind<'info>>,
+        params: SyncAutoBindParams,
+    ) -> Result<()> {
+        let seeds = [b"state", params.source.as_ref(), &[ctx.bumps.state]];
         let state_signer = [seeds.as_ref()];
-        mint_nft(
+        auto_bind(
+            ctx.remaining_accounts,
+            &ctx.accounts.owner_store.load(),
             ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.bind_mint.borrow_mut(),
-            ctx.accounts.bind_token.to_account_info(),
             ctx.accounts.state.to_account_info(),
+            ctx.accounts.state.auto_bind,
             state_signer.as_slice(),
-        )?;
-
-        Ok(())
+        )
     }
 
-    pub fn unbind_nft(ctx: Context<UnbindNFT>, params: UnbindParams) -> Result<()> {
-        msg!("check permission for unbind nft");
+    pub fn set_royalty(ctx: Context<SetRoyalty>, params: RoyaltyParams) -> Result<()> {
+        msg!("check permission for set royalty");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
+        require_gte!(10_000, params.royalty_bps, SolX404Error::InvalidRoyalty);
+        require_gte!(
+            MAX_MARKETPLACES,
+            params.marketplaces.len(),
+            SolX404Error::TooManyMarketplaces
+        );
 
-        // send the corresponding nft to the signer
-        burn_token(
+        require!(
+            !ctx.accounts.collection_mint.data_is_empty(),
+            SolX404Error::CollectionNotMinted
+        );
+
+        let state = &mut ctx.accounts.state;
+        state.royalty_bps = params.royalty_bps;
+        state.royalty_receiver = params.royalty_receiver;
+        state.marketplaces = params.marketplaces;
+
+        msg!("publish royalty in collection metadata");
+        let state_seeds = [b"state", params.source.as_ref(), &[ctx.bumps.state]];
+        publish_royalty(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.collection_mint.to_account_info(),
+            ctx.accounts.state.to_account_info(),
+            params.royalty_bps,
+            params.royalty_receiver,
+            [state_seeds.as_ref()].as_slice(),
+        )
+    }
+
+    pub fn set_reroll(ctx: Context<SetReroll>, params: SetRerollParams) -> Result<()> {
+        msg!("check permission for set reroll");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
+
+        require!(
+            valid_randomness(params.randomness),
+            SolX404Error::InvalidRandomness
+        );
+
+        ctx.accounts.state.reroll_fee = params.reroll_fee;
+        ctx.accounts.state.randomness = params.randomness;
+        Ok(())
+    }
+
+    // commit to a reroll, the draw is settled by `settle_reroll` in a later
+    // slot from the hash of this one, unknown when the request is sent
+    pub fn reroll(ctx: Context<Reroll>, params: RerollParams) -> Result<()> {
+        let state_key = ctx.accounts.state.key();
+        let signer = ctx.accounts.signer.key();
+        let fee = ctx.accounts.state.reroll_fee;
+
+        require!(
+            valid_randomness(ctx.accounts.state.randomness),
+            SolX404Error::InvalidRandomness
+        );
+        let mut store = ctx.accounts.owner_store.load();
+        require!(
+            store
+                .map
+                .get(&signer)
+                .is_some_and(|record| record.contains(&params.nft_mint)),
+            SolX404Error::NotNFTHolder
+        );
+        require!(
+            can_reroll(&store, state_key, signer, params.nft_mint),
+            SolX404Error::EmptyPool
+        );
+
+        let request = &mut ctx.accounts.request;
+        request.holder = signer;
+        request.nft_mint = params.nft_mint;
+        request.slot = Clock::get()?.slot;
+        msg!("{} requested a reroll of {}", signer, params.nft_mint);
+
+        if fee == 0 {
+            return Ok(());
+        }
+
+        // charge the fee, mint and burn to skip the hook like redeem
+        let receiver = ctx.accounts.state.owner;
+        let fungible_supply = ctx.accounts.state.fungible_supply;
+        let sender_before =
+            decrease_balance(&mut store, ctx.accounts.fungible_token.key(), signer, fee)?;
+        let receiver_before =
+            increase_balance(&mut store, ctx.accounts.fee_receiver.key(), receiver, fee)?;
+        let to_remove = nft_delta(
+            sender_before,
+            sender_before
+                .checked_sub(fee)
+                .ok_or(SolX404Error::ArithmeticUnderflow)?,
+            fungible_supply,
+        )?;
+        let to_add = nft_delta(
+            receiver_before,
+            receiver_before
+                .checked_add(fee)
+                .ok_or(SolX404Error::ArithmeticOverflow)?,
+            fungible_supply,
+        )?;
+
+        burn_token(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.fungible_mint.to_account_info(),
+            ctx.accounts.fungible_token.to_account_info(),
+            fee,
+            ctx.accounts.signer.to_account_info(),
+        )?;
+        let seeds = [
+            b"state",
+            ctx.accounts.state.source.as_ref(),
+            &[ctx.bumps.state],
+        ];
+        mint_token(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.fungible_mint.to_account_info(),
+            ctx.accounts.fee_receiver.to_account_info(),
+            fee,
+            ctx.accounts.state.to_account_info(),
+            &[seeds.as_ref()],
+        )?;
+
+        let exemptions = load_exemptions(&ctx.accounts.exemption)?;
+        let sender_holder =
+            nft_holder(&exemptions, &ctx.accounts.signer_config, signer, state_key)?;
+        let receiver_holder =
+            nft_holder(&exemptions, &ctx.accounts.owner_config, receiver, state_key)?;
+        do_rebalance(
+            &mut store,
+            sender_holder,
+            receiver_holder,
+            state_key,
+            to_add,
+            to_remove,
+        )?;
+
+        check_nft_balance(&store, signer, sender_holder, fungible_supply)?;
+        check_nft_balance(&store, receiver, receiver_holder, fungible_supply)?;
+        // the fee must not take the mint to reroll from the holder
+        require!(
+            can_reroll(&store, state_key, signer, params.nft_mint),
+            SolX404Error::NotNFTHolder
+        );
+
+        msg!("reroll fee charged.");
+        save_owner_store(
+            &mut ctx.accounts.owner_store,
+            &store,
+            Some((
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
+        )
+    }
+
+    // permissionless, draw the pool mint of a pending reroll. A request whose
+    // mint left the holder, whose pool is empty or whose slot hash expired is
+    // closed without a draw, the fee is not refunded
+    pub fn settle_reroll(ctx: Context<SettleReroll>, params: SettleRerollParams) -> Result<()> {
+        let state_key = ctx.accounts.state.key();
+        let request = &ctx.accounts.request;
+        require_gt!(
+            Clock::get()?.slot,
+            request.slot,
+            SolX404Error::RerollNotReady
+        );
+
+        let Some(randomness) = recent_slot_hash(&ctx.accounts.randomness, request.slot)? else {
+            msg!("slot hash of the reroll expired");
+            return Ok(());
+        };
+        let mut store = ctx.accounts.owner_store.load();
+        if !can_reroll(&store, state_key, params.holder, request.nft_mint) {
+            msg!("{} can no longer be rerolled", request.nft_mint);
+            return Ok(());
+        }
+
+        let drawn = swap_with_pool(
+            &mut store,
+            state_key,
+            params.holder,
+            request.nft_mint,
+            randomness,
+        )?;
+        msg!(
+            "{} rerolled {} into {}",
+            params.holder,
+            request.nft_mint,
+            drawn
+        );
+
+        // a swap never changes the size of the store
+        save_owner_store(&mut ctx.accounts.owner_store, &store, None)
+    }
+
+    pub fn reveal_nft(ctx: Context<RevealNFT>, params: RevealNFTParams) -> Result<()> {
+        require!(ctx.accounts.state.revealed(), SolX404Error::NotRevealed);
+        require!(
+            !ctx.accounts.nft_mint.data_is_empty(),
+            SolX404Error::InvalidNFTMint
+        );
+        require!(
+            verify_reveal_proof(
+                ctx.accounts.state.reveal_hash,
+                reveal_leaf(params.number, &params.path),
+                &params.proof,
+            ),
+            SolX404Error::InvalidRevealProof
+        );
+
+        let seeds = [
+            b"state",
+            ctx.accounts.state.source.as_ref(),
+            &[ctx.bumps.state],
+        ];
+        update_nft_uri(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.nft_mint.to_account_info(),
+            ctx.accounts.state.to_account_info(),
+            format!("{}/{}", ctx.accounts.state.base_uri, params.path),
+            &[seeds.as_ref()],
+        )?;
+        msg!("#{} revealed", params.number);
+        Ok(())
+    }
+
+    pub fn link_deposit(ctx: Context<LinkDeposit>, params: LinkParams) -> Result<()> {
+        msg!("check permission for link deposit");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.state.owner,
+            SolX404Error::OnlyCallByOwner
+        );
+        require_keys_eq!(
+            ctx.accounts.nft_bank.linked_nft,
+            Pubkey::default(),
+            SolX404Error::NFTAlreadyLinked
+        );
+        require!(
+            !ctx.accounts.nft_mint.data_is_empty(),
+            SolX404Error::InvalidNFTMint
+        );
+
+        let deposit = deposit_metadata(
+            &ctx.accounts.deposit_metadata,
+            ctx.accounts.deposit_mint.key(),
+        )?;
+
+        let seeds = [
+            b"state",
+            ctx.accounts.state.source.as_ref(),
+            &[ctx.bumps.state],
+        ];
+        link_metadata(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.nft_mint.to_account_info(),
+            ctx.accounts.state.to_account_info(),
+            ctx.accounts.deposit_mint.key(),
+            deposit,
+            &[seeds.as_ref()],
+        )?;
+
+        ctx.accounts.nft_bank.linked_nft = ctx.accounts.nft_mint.key();
+        msg!(
+            "#{} mirrors {}",
+            params.number,
+            ctx.accounts.deposit_mint.key()
+        );
+        Ok(())
+    }
+
+    pub fn bind_nft(ctx: Context<BindNFT>, params: BindParams) -> Result<()> {
+        msg!("check permission for bind nft");
+
+        let mut store = ctx.accounts.owner_store.load();
+        let holder = store.index.get(&ctx.accounts.bind_mint.key()).copied();
+        check_bind_holder(holder, ctx.accounts.signer.key(), params.number)?;
+
+        // fetch the nft
+
+        take_from_owner_store(
+            &mut store,
+            ctx.accounts.signer.key(),
+            ctx.accounts.bind_mint.key(),
+        )?;
+
+        // the NFT stays in use while bound, the registry tracks who may unbind it
+        let mut registry = load_bound_registry(&ctx.accounts.bound_registry)?;
+        add_bound(
+            &mut registry,
+            ctx.accounts.bind_mint.key(),
+            ctx.accounts.signer.key(),
+            ctx.accounts.state.nft_in_use,
+        )?;
+        let state_key = ctx.accounts.state.key();
+        // burn the token for bind
+
+        decrease_balance(
+            &mut store,
+            ctx.accounts.fungible_token.key(),
+            ctx.accounts.signer.key(),
+            ctx.accounts.state.fungible_supply,
+        )?;
+
+        burn_token(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.fungible_mint.to_account_info(),
+            ctx.accounts.fungible_token.to_account_info(),
+            ctx.accounts.state.fungible_supply,
+            ctx.accounts.signer.to_account_info(),
+        )?;
+
+        // send the corresponding nft to the signer
+        let seeds = [
+            b"state",
+            ctx.accounts.state.source.as_ref(),
+            &[ctx.bumps.state],
+        ];
+
+        let state_signer = [seeds.as_ref()];
+        // display only when soulbound, the holder has to unbind to move the value
+        deliver_bound_nft(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.bind_mint.borrow_mut(),
+            ctx.accounts.bind_token.to_account_info(),
+            ctx.accounts.state.to_account_info(),
+            ctx.accounts.state.soulbound,
+            state_signer.as_slice(),
+        )?;
+
+        save_owner_store(
+            &mut ctx.accounts.owner_store,
+            &store,
+            Some((
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
+        )?;
+
+        let registry_seeds = [b"bound", state_key.as_ref(), &[ctx.bumps.bound_registry]];
+        store_bound_registry(
+            ctx.accounts.bound_registry.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            registry_seeds.as_ref(),
+            registry,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn unbind_nft(ctx: Context<UnbindNFT>, params: UnbindParams) -> Result<()> {
+        msg!("check permission for unbind nft");
+
+        let seeds = [b"state", params.source.as_ref(), &[ctx.bumps.state]];
+        let state_signer = [seeds.as_ref()];
+
+        // only the holder recorded by the last transfer may unbind
+        let mut registry = load_bound_registry(&ctx.accounts.bound_registry)?;
+        take_bound(
+            &mut registry,
+            ctx.accounts.unbind_mint.key(),
+            ctx.accounts.signer.key(),
+            params.number,
+        )?;
+        let mut store = ctx.accounts.owner_store.load();
+        check_unbind(
+            &store,
+            ctx.accounts.unbind_mint.key(),
+            ctx.accounts.unbind_holder.amount,
+            params.number,
+        )?;
+        let state_key = ctx.accounts.state.key();
+        // bound while the state was soulbound
+        if ctx.accounts.unbind_holder.is_frozen() {
+            set_frozen(
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.unbind_mint.to_account_info(),
+                ctx.accounts.unbind_holder.to_account_info(),
+                ctx.accounts.state.to_account_info(),
+                false,
+                state_signer.as_slice(),
+            )?;
+        }
+
+        // send the corresponding nft to the signer
+        burn_token(
             ctx.accounts.token_program.to_account_info(),
             ctx.accounts.unbind_mint.to_account_info(),
             ctx.accounts.unbind_holder.to_account_info(),
@@ -447,25 +1425,303 @@ mod x404 {
         )?;
 
         // add back the nft to owner store
+        let exemptions = load_exemptions(&ctx.accounts.exemption)?;
         add_to_owner_store(
-            &mut ctx.accounts.owner_store,
+            &mut store,
             ctx.accounts.unbind_mint.key(),
+            nft_holder(
+                &exemptions,
+                &ctx.accounts.signer_config,
+                ctx.accounts.signer.key(),
+                ctx.accounts.state.key(),
+            )?,
+        )?;
+        increase_balance(
+            &mut store,
+            ctx.accounts.fungible_token.key(),
             ctx.accounts.signer.key(),
+            ctx.accounts.state.fungible_supply,
+        )?;
+
+        // remint token for signer
+
+        mint_token(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.fungible_mint.to_account_info(),
+            ctx.accounts.fungible_token.to_account_info(),
+            ctx.accounts.state.fungible_supply,
+            ctx.accounts.state.to_account_info(),
+            state_signer.as_slice(),
+        )?;
+
+        save_owner_store(
+            &mut ctx.accounts.owner_store,
+            &store,
+            Some((
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
         )?;
 
+        let registry_seeds = [b"bound", state_key.as_ref(), &[ctx.bumps.bound_registry]];
+        store_bound_registry(
+            ctx.accounts.bound_registry.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            registry_seeds.as_ref(),
+            registry,
+        )?;
+
+        Ok(())
+    }
+
+    // bind several NFTs with one owner store update and one fungible burn
+    pub fn batch_bind_nft<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchBindNFT<'info>>,
+        params: BatchBindParams,
+    ) -> Result<()> {
+        msg!("check permission for batch bind nft");
+
+        let state_key = ctx.accounts.state.key();
+        let signer = ctx.accounts.signer.key();
+        let nfts = batch_nft_accounts(ctx.remaining_accounts, state_key, signer, &params.numbers)?;
+
+        let mut store = ctx.accounts.owner_store.load();
+        for (number, (mint, _)) in params.numbers.iter().zip(nfts.iter()) {
+            check_bind_holder(store.index.get(mint.key).copied(), signer, *number)?;
+        }
+
+        // fetch the nfts
+        let mints: Vec<Pubkey> = nfts.iter().map(|(mint, _)| mint.key()).collect();
+        take_many_from_owner_store(&mut store, signer, &mints)?;
+
+        let mut registry = load_bound_registry(&ctx.accounts.bound_registry)?;
+        for mint in mints.iter() {
+            add_bound(&mut registry, *mint, signer, ctx.accounts.state.nft_in_use)?;
+        }
+        // burn the token for all the binds
+        let amount = ctx
+            .accounts
+            .state
+            .fungible_supply
+            .checked_mul(mints.len() as u64)
+            .ok_or(SolX404Error::ArithmeticOverflow)?;
+        decrease_balance(
+            &mut store,
+            ctx.accounts.fungible_token.key(),
+            signer,
+            amount,
+        )?;
+
+        burn_token(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.fungible_mint.to_account_info(),
+            ctx.accounts.fungible_token.to_account_info(),
+            amount,
+            ctx.accounts.signer.to_account_info(),
+        )?;
+
+        // send the corresponding nfts to the signer
         let seeds = [b"state", params.source.as_ref(), &[ctx.bumps.state]];
+        let state_signer = [seeds.as_ref()];
+        for (mint, token) in nfts {
+            if token.data_is_empty() {
+                create_associated_token_account(CpiContext::new(
+                    ctx.accounts.associated_token_program.to_account_info(),
+                    Create {
+                        payer: ctx.accounts.signer.to_account_info(),
+                        associated_token: token.clone(),
+                        authority: ctx.accounts.signer.to_account_info(),
+                        mint: mint.clone(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                    },
+                ))?;
+            }
+
+            deliver_bound_nft(
+                ctx.accounts.token_program.to_account_info(),
+                &mut InterfaceAccount::try_from(mint)?,
+                token.clone(),
+                ctx.accounts.state.to_account_info(),
+                ctx.accounts.state.soulbound,
+                state_signer.as_slice(),
+            )?;
+        }
 
+        save_owner_store(
+            &mut ctx.accounts.owner_store,
+            &store,
+            Some((
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
+        )?;
+
+        let registry_seeds = [b"bound", state_key.as_ref(), &[ctx.bumps.bound_registry]];
+        store_bound_registry(
+            ctx.accounts.bound_registry.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            registry_seeds.as_ref(),
+            registry,
+        )?;
+
+        Ok(())
+    }
+
+    // unbind several NFTs with one owner store update and one fungible mint
+    pub fn batch_unbind_nft<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchUnbindNFT<'info>>,
+        params: BatchUnbindParams,
+    ) -> Result<()> {
+        msg!("check permission for batch unbind nft");
+
+        let state_key = ctx.accounts.state.key();
+        let signer = ctx.accounts.signer.key();
+        let nfts = batch_nft_accounts(ctx.remaining_accounts, state_key, signer, &params.numbers)?;
+
+        // only the holder recorded by the last transfer may unbind
+        let mut registry = load_bound_registry(&ctx.accounts.bound_registry)?;
+        let mut store = ctx.accounts.owner_store.load();
+        for (number, (mint, token)) in params.numbers.iter().zip(nfts.iter()) {
+            take_bound(&mut registry, mint.key(), signer, *number)?;
+            check_unbind(
+                &store,
+                mint.key(),
+                InterfaceAccount::<TokenAccount>::try_from(token)?.amount,
+                *number,
+            )?;
+        }
+        let seeds = [b"state", params.source.as_ref(), &[ctx.bumps.state]];
         let state_signer = [seeds.as_ref()];
+        for &(mint, token) in nfts.iter() {
+            // bound while the state was soulbound
+            let token_account = InterfaceAccount::<TokenAccount>::try_from(token)?;
+            if token_account.is_frozen() {
+                set_frozen(
+                    ctx.accounts.token_program.to_account_info(),
+                    mint.clone(),
+                    token.clone(),
+                    ctx.accounts.state.to_account_info(),
+                    false,
+                    state_signer.as_slice(),
+                )?;
+            }
+
+            burn_token(
+                ctx.accounts.token_program.to_account_info(),
+                mint.clone(),
+                token.clone(),
+                1,
+                ctx.accounts.signer.to_account_info(),
+            )?;
+
+            close_token_account(
+                ctx.accounts.token_program.to_account_info(),
+                token.clone(),
+                ctx.accounts.signer.to_account_info(),
+                &[],
+            )?;
+        }
+
+        // add back the nfts to owner store
+        let mints: Vec<Pubkey> = nfts.iter().map(|(mint, _)| mint.key()).collect();
+        let exemptions = load_exemptions(&ctx.accounts.exemption)?;
+        add_many_to_owner_store(
+            &mut store,
+            &mints,
+            nft_holder(&exemptions, &ctx.accounts.signer_config, signer, state_key)?,
+        )?;
+
         // remint token for signer
+        let amount = ctx
+            .accounts
+            .state
+            .fungible_supply
+            .checked_mul(mints.len() as u64)
+            .ok_or(SolX404Error::ArithmeticOverflow)?;
+        increase_balance(
+            &mut store,
+            ctx.accounts.fungible_token.key(),
+            signer,
+            amount,
+        )?;
 
         mint_token(
             ctx.accounts.token_program.to_account_info(),
             ctx.accounts.fungible_mint.to_account_info(),
             ctx.accounts.fungible_token.to_account_info(),
-            ctx.accounts.state.fungible_supply,
+            amount,
             ctx.accounts.state.to_account_info(),
             state_signer.as_slice(),
         )?;
+
+        save_owner_store(
+            &mut ctx.accounts.owner_store,
+            &store,
+            Some((
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
+        )?;
+
+        let registry_seeds = [b"bound", state_key.as_ref(), &[ctx.bumps.bound_registry]];
+        store_bound_registry(
+            ctx.accounts.bound_registry.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            registry_seeds.as_ref(),
+            registry,
+        )?;
+
+        Ok(())
+    }
+
+    // called by the hook when a bound NFT changes hands, the receiver becomes
+    // the one entitled to unbind it
+    pub fn transfer_bound(ctx: Context<TransferBound>) -> Result<()> {
+        msg!("check permission for transfer bound");
+
+        // permission check
+        let nft_mint = ctx.accounts.nft_mint.key();
+        let (meta_list, _) = Pubkey::find_program_address(
+            &[b"extra-account-metas", nft_mint.as_ref()],
+            &ctx.accounts.state.hook_program,
+        );
+        require_keys_eq!(
+            ctx.accounts.hooker.key(),
+            meta_list,
+            SolX404Error::OnlyCallByNFTHook
+        );
+        require_eq!(
+            ctx.accounts.hooker.is_signer,
+            true,
+            SolX404Error::OnlyCallByNFTHook
+        );
+
+        let mut registry = load_bound_registry(&ctx.accounts.bound_registry)?;
+        let sender = ctx.accounts.source_token.owner;
+        let receiver = ctx.accounts.destination_token.owner;
+        let bound = match registry.nfts.iter_mut().find(|nft| nft.mint == nft_mint) {
+            Some(bound) => bound,
+            None => {
+                msg!("{} is not bound", nft_mint);
+                return err!(SolX404Error::NFTNotBound);
+            }
+        };
+
+        // the NFT can only leave the account of the recorded holder
+        if bound.holder != sender {
+            msg!("{} is bound to {} not {}", nft_mint, bound.holder, sender);
+            return err!(SolX404Error::BoundRegistryMismatch);
+        }
+
+        msg!("{} bound: {} -> {}", nft_mint, bound.holder, receiver);
+        bound.holder = receiver;
+
+        // same size, the entry is updated in place
+        registry.try_serialize(&mut ctx.accounts.bound_registry.try_borrow_mut_data()?.as_mut())?;
         Ok(())
     }
 
@@ -492,29 +1748,431 @@ mod x404 {
             params.amount
         );
 
-        // rebalance is triggered after transfer.
-        let to_remove = ((ctx.accounts.sender_account.amount + params.amount)
-            / ctx.accounts.state.fungible_supply
-            - ctx.accounts.sender_account.amount / ctx.accounts.state.fungible_supply)
-            as usize;
+        let state_key = ctx.accounts.state.key();
+        let fungible_supply = ctx.accounts.state.fungible_supply;
+        let sender_account = ctx.accounts.sender_account.key();
+        let receiver_account = ctx.accounts.receiver_account.key();
 
-        let to_add = (ctx.accounts.receiver_account.amount / ctx.accounts.state.fungible_supply
-            - (ctx.accounts.receiver_account.amount - params.amount)
-                / ctx.accounts.state.fungible_supply) as usize;
+        // exempted and opted out owners are routed to the pool of the state
+        let exemptions = load_exemptions(&ctx.accounts.exemption)?;
+        let sender = nft_holder(
+            &exemptions,
+            &ctx.accounts.sender_config,
+            params.sender,
+            state_key,
+        )?;
+        let receiver = nft_holder(
+            &exemptions,
+            &ctx.accounts.receiver_config,
+            params.receiver,
+            state_key,
+        )?;
+
+        // the balances are tracked per token account and summed per owner so
+        // that owners holding several token accounts are aggregated. Bring
+        // both records to the live amounts first, before the transfer unless
+        // the tokens stay with the same owner.
+        let same_owner = params.sender == params.receiver;
+        let (sender_amount, receiver_amount) = if same_owner {
+            (
+                ctx.accounts.sender_account.amount,
+                ctx.accounts.receiver_account.amount,
+            )
+        } else {
+            (
+                ctx.accounts
+                    .sender_account
+                    .amount
+                    .checked_add(params.amount)
+                    .ok_or(SolX404Error::ArithmeticOverflow)?,
+                ctx.accounts
+                    .receiver_account
+                    .amount
+                    .checked_sub(params.amount)
+                    .ok_or(SolX404Error::ArithmeticUnderflow)?,
+            )
+        };
+        let mut store = ctx.accounts.owner_store.load();
+        let mut synced =
+            sync_token_account(&mut store, sender_account, params.sender, sender_amount)?;
+        synced.extend(sync_token_account(
+            &mut store,
+            receiver_account,
+            params.receiver,
+            receiver_amount,
+        )?);
+        for owner in synced {
+            release_surplus(&mut store, state_key, owner, fungible_supply)?;
+            if owner == sender || owner == receiver {
+                claim_from_pool(&mut store, state_key, owner, fungible_supply)?;
+            }
+        }
+
+        // moving tokens between accounts of the same owner never changes
+        // how many NFTs the owner is entitled to.
+        if same_owner {
+            msg!("same owner transfer, skip rebalance");
+            return save_owner_store(&mut ctx.accounts.owner_store, &store, None);
+        }
+
+        let sender_before =
+            decrease_balance(&mut store, sender_account, params.sender, params.amount)?;
+        let receiver_before =
+            increase_balance(&mut store, receiver_account, params.receiver, params.amount)?;
+
+        let to_remove = nft_delta(
+            sender_before,
+            sender_before
+                .checked_sub(params.amount)
+                .ok_or(SolX404Error::ArithmeticUnderflow)?,
+            fungible_supply,
+        )?;
+        let to_add = nft_delta(
+            receiver_before,
+            receiver_before
+                .checked_add(params.amount)
+                .ok_or(SolX404Error::ArithmeticOverflow)?,
+            fungible_supply,
+        )?;
 
         msg!("{} lose {}", params.sender, to_remove);
         msg!("{} get {}", params.receiver, to_add);
 
         // if to_add = to_remove, the amount in second call of `transfer_from_owner_store` will be 0
         // which will be skipped in the function, so no need to check here
-        do_rebalance(
+        do_rebalance(&mut store, sender, receiver, state_key, to_add, to_remove)?;
+
+        check_nft_balance(&store, params.sender, sender, fungible_supply)?;
+        check_nft_balance(&store, params.receiver, receiver, fungible_supply)?;
+        // no payer in the hook, the store grows from its own lamports
+        save_owner_store(&mut ctx.accounts.owner_store, &store, None)
+    }
+
+    pub fn add_exemption(ctx: Context<UpdateExemption>, params: ExemptionParams) -> Result<()> {
+        msg!("check permission for add exemption");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
+
+        let mut exemptions = load_exemptions(&ctx.accounts.exemption)?;
+        if exemptions.contains(&params.owner) {
+            msg!("{} already exempted", params.owner);
+            return Ok(());
+        }
+
+        // the owner keeps the balance but the NFTs go to the pool
+        let mut store = ctx.accounts.owner_store.load();
+        release_to_pool(&mut store, ctx.accounts.state.key(), params.owner)?;
+        save_owner_store(
             &mut ctx.accounts.owner_store,
-            params.sender,
-            params.receiver,
-            ctx.accounts.state.to_account_info().key(),
-            to_add,
-            to_remove,
+            &store,
+            Some((
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
+        )?;
+
+        exemptions.push(params.owner);
+        let state_key = ctx.accounts.state.key();
+        let seeds = [b"exemption", state_key.as_ref(), &[ctx.bumps.exemption]];
+        store_exemptions(
+            ctx.accounts.exemption.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            seeds.as_ref(),
+            exemptions,
+        )?;
+
+        msg!("{} exempted", params.owner);
+        Ok(())
+    }
+
+    pub fn remove_exemption(ctx: Context<UpdateExemption>, params: ExemptionParams) -> Result<()> {
+        msg!("check permission for remove exemption");
+        require_eq!(
+            ctx.accounts.signer.key(),
+            ctx.accounts.hub.manager,
+            SolX404Error::OnlyCallByOwner
+        );
+
+        let mut exemptions = load_exemptions(&ctx.accounts.exemption)?;
+        if !exemptions.contains(&params.owner) {
+            msg!("{} not exempted", params.owner);
+            return Ok(());
+        }
+
+        // re-claim the NFTs backed by the current balance
+        if !skip_nft(&ctx.accounts.holder_config)? {
+            let mut store = ctx.accounts.owner_store.load();
+            claim_from_pool(
+                &mut store,
+                ctx.accounts.state.key(),
+                params.owner,
+                ctx.accounts.state.fungible_supply,
+            )?;
+            save_owner_store(
+                &mut ctx.accounts.owner_store,
+                &store,
+                Some((
+                    ctx.accounts.signer.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                )),
+            )?;
+        }
+
+        exemptions.retain(|owner| owner != &params.owner);
+        let state_key = ctx.accounts.state.key();
+        let seeds = [b"exemption", state_key.as_ref(), &[ctx.bumps.exemption]];
+        store_exemptions(
+            ctx.accounts.exemption.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            seeds.as_ref(),
+            exemptions,
+        )?;
+
+        msg!("{} exemption removed", params.owner);
+        Ok(())
+    }
+
+    pub fn set_skip_nft(ctx: Context<SetSkipNFT>, params: SkipNFTParams) -> Result<()> {
+        msg!("update skip nft for {}", ctx.accounts.signer.key());
+
+        if ctx.accounts.holder_config.skip_nft == params.skip {
+            return Ok(());
+        }
+        ctx.accounts.holder_config.skip_nft = params.skip;
+
+        let exemptions = load_exemptions(&ctx.accounts.exemption)?;
+        if exemptions.contains(&ctx.accounts.signer.key()) {
+            // the NFTs of exempted owners stay in the pool anyway
+            return Ok(());
+        }
+
+        let mut store = ctx.accounts.owner_store.load();
+        if params.skip {
+            release_to_pool(
+                &mut store,
+                ctx.accounts.state.key(),
+                ctx.accounts.signer.key(),
+            )?;
+        } else {
+            claim_from_pool(
+                &mut store,
+                ctx.accounts.state.key(),
+                ctx.accounts.signer.key(),
+                ctx.accounts.state.fungible_supply,
+            )?;
+        }
+
+        save_owner_store(
+            &mut ctx.accounts.owner_store,
+            &store,
+            Some((
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
+        )
+    }
+
+    // permissionless, bring the recorded balance of a token account to its live
+    // amount and owner, e.g. after a direct burn, and reassign the NFTs
+    pub fn sync_balance(ctx: Context<SyncBalance>, _params: SyncBalanceParams) -> Result<()> {
+        let state_key = ctx.accounts.state.key();
+        let fungible_supply = ctx.accounts.state.fungible_supply;
+        let owner = ctx.accounts.token_account.owner;
+
+        let exemptions = load_exemptions(&ctx.accounts.exemption)?;
+        let holder = nft_holder(&exemptions, &ctx.accounts.holder_config, owner, state_key)?;
+
+        let mut store = ctx.accounts.owner_store.load();
+        let synced = sync_token_account(
+            &mut store,
+            ctx.accounts.token_account.key(),
+            owner,
+            ctx.accounts.token_account.amount,
         )?;
+        for synced_owner in synced {
+            release_surplus(&mut store, state_key, synced_owner, fungible_supply)?;
+            if synced_owner == holder {
+                claim_from_pool(&mut store, state_key, synced_owner, fungible_supply)?;
+            }
+        }
+
+        check_nft_balance(&store, owner, holder, fungible_supply)?;
+        // the caller pays for the record of a token account seen first
+        save_owner_store(
+            &mut ctx.accounts.owner_store,
+            &store,
+            Some((
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            )),
+        )
+    }
+
+    pub fn get_owner_nfts(ctx: Context<ViewOwnerNFTs>, params: ViewOwnerParams) -> Result<()> {
+        let nfts = ctx
+            .accounts
+            .owner_store
+            .get_map()
+            .remove(&params.owner)
+            .unwrap_or_default();
+
+        let view = OwnerNFTsView {
+            owner: params.owner,
+            nfts,
+        };
+
+        set_return_data(&view.try_to_vec()?);
+        Ok(())
+    }
+
+    pub fn get_nft_holder(ctx: Context<ViewNFTHolder>, _params: ViewHolderParams) -> Result<()> {
+        let mint = ctx.accounts.nft_mint.key();
+        let view = NFTHolderView {
+            mint,
+            holder: ctx.accounts.owner_store.get_index().get(&mint).copied(),
+        };
+
+        set_return_data(&view.try_to_vec()?);
+        Ok(())
+    }
+
+    pub fn get_state_summary(ctx: Context<ViewState>, _params: ViewStateParams) -> Result<()> {
+        let state = &ctx.accounts.state;
+        let view = StateSummaryView {
+            source: state.source,
+            owner: state.owner,
+            fungible_mint: state.fungible_mint,
+            collection_mint: state.collection_mint,
+            decimal: state.decimal,
+            fungible_supply: state.fungible_supply,
+            redeem_fee: state.redeem_fee,
+            redeem_max_deadline: state.redeem_max_deadline,
+            nft_supply: state.nft_supply,
+            nft_in_use: state.nft_in_use,
+            revealed: state.revealed(),
+        };
+
+        set_return_data(&view.try_to_vec()?);
+        Ok(())
+    }
+
+    // recompute the accounting of a state and report every discrepancy, for
+    // monitoring. Never fails on a discrepancy so it can always be simulated.
+    pub fn audit_state(ctx: Context<AuditState>, _params: ViewStateParams) -> Result<()> {
+        let state = &ctx.accounts.state;
+        let store = &ctx.accounts.owner_store;
+        let map = store.get_map();
+        let balances = store.get_balances();
+        let bound = load_bound_registry(&ctx.accounts.bound_registry)?
+            .nfts
+            .len() as u64;
+
+        let assigned = map.values().map(|nfts| nfts.len() as u64).sum::<u64>();
+        let pool = map.get(&state.key()).map_or(0, |nfts| nfts.len() as u64);
+        let balance_total = balances.values().try_fold(0u64, |total, balance| {
+            total
+                .checked_add(*balance)
+                .ok_or(SolX404Error::ArithmeticOverflow)
+        })?;
+        let mut discrepancies = Vec::new();
+
+        // every created mint is either assigned or bound
+        if assigned + bound != state.nft_supply {
+            discrepancies.push(AuditDiscrepancy::NFTSupply {
+                expected: state.nft_supply,
+                actual: assigned + bound,
+            });
+        }
+        if state.nft_in_use > state.nft_supply {
+            discrepancies.push(AuditDiscrepancy::NFTInUse {
+                nft_in_use: state.nft_in_use,
+                nft_supply: state.nft_supply,
+            });
+        }
+
+        // each deposit in use backs `fungible_supply` tokens, burned while bound
+        let expected_supply = state
+            .nft_in_use
+            .saturating_sub(bound)
+            .checked_mul(state.fungible_supply)
+            .ok_or(SolX404Error::ArithmeticOverflow)?;
+        if ctx.accounts.fungible_mint.supply != expected_supply {
+            discrepancies.push(AuditDiscrepancy::FungibleSupply {
+                expected: expected_supply,
+                actual: ctx.accounts.fungible_mint.supply,
+            });
+        }
+        if balance_total != ctx.accounts.fungible_mint.supply {
+            discrepancies.push(AuditDiscrepancy::OwnerStoreBalance {
+                expected: ctx.accounts.fungible_mint.supply,
+                actual: balance_total,
+            });
+        }
+
+        let index = store.get_index();
+        let indexed = map
+            .iter()
+            .flat_map(|(owner, nfts)| nfts.iter().map(move |nft| (nft, owner)))
+            .filter(|(nft, owner)| index.get(nft) == Some(owner))
+            .count() as u64;
+        if indexed != assigned || index.len() as u64 != assigned {
+            discrepancies.push(AuditDiscrepancy::OwnerStoreIndex {
+                expected: assigned,
+                actual: indexed,
+            });
+        }
+
+        let mut over_assigned = 0;
+        for (owner, nfts) in map.iter().filter(|(owner, _)| **owner != state.key()) {
+            let balance = balances.get(owner).copied().unwrap_or(0);
+            // the audit never fails, a zero fungible supply allows no NFT
+            let allowed = balance.checked_div(state.fungible_supply).unwrap_or(0);
+            if nfts.len() as u64 > allowed {
+                msg!("{} assigned {} NFT for {}", owner, nfts.len(), balance);
+                over_assigned += 1;
+            }
+        }
+        if over_assigned > 0 {
+            discrepancies.push(AuditDiscrepancy::OverAssigned {
+                owners: over_assigned,
+            });
+        }
+
+        for discrepancy in discrepancies.iter() {
+            msg!("audit discrepancy: {:?}", discrepancy);
+        }
+
+        let view = AuditView {
+            nft_supply: state.nft_supply,
+            nft_in_use: state.nft_in_use,
+            fungible_mint_supply: ctx.accounts.fungible_mint.supply,
+            balances: balance_total,
+            assigned,
+            pool,
+            bound,
+            discrepancies,
+        };
+
+        set_return_data(&view.try_to_vec()?);
+        Ok(())
+    }
+
+    pub fn get_bank_info(ctx: Context<ViewBank>, _params: ViewBankParams) -> Result<()> {
+        let bank = &ctx.accounts.nft_bank;
+        let view = BankInfoView {
+            id: bank.id,
+            owner: bank.owner,
+            redeem_deadline: bank.redeem_deadline,
+            issued: bank.issued,
+            linked_nft: bank.linked_nft,
+        };
+
+        set_return_data(&view.try_to_vec()?);
         Ok(())
     }
 }
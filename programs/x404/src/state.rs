@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    solana_program::{hash::hashv, program_option::COption},
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{Mint as SPLMint, Token, TokenAccount as SPLTokenAccount},
@@ -8,8 +11,54 @@ use anchor_spl::{
     token_interface::{Mint, TokenAccount},
 };
 
-pub const BANK_SIZE: usize = 8 + 8 + 32 + 32 + 1;
-pub const X404_STATE_SIZE: usize = 8 + 32 + 8 + 8 + 32 + 1 + 32 + 32 + 32 + 8 + 8 + 8;
+use crate::{error::SolX404Error, program::X404};
+
+pub const BANK_SIZE: usize = 8 + 8 + 32 + 32 + 1 + 32;
+pub const HOLDER_CONFIG_SIZE: usize = 8 + 1;
+pub const REROLL_REQUEST_SIZE: usize = 8 + 32 + 32 + 8;
+// metaplex token metadata program, owner of the deposited NFT metadata
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+pub const MAX_URI_LENGTH: usize = 200;
+pub const MAX_MARKETPLACES: usize = 8;
+// NFT copies minted or burned by one auto bind reconciliation, the others stay
+// virtual until a later call
+pub const MAX_AUTO_BIND_PER_CALL: usize = 4;
+// layout versions of the accounts, bumped with every layout change that
+// `migrate` must handle
+pub const X404_STATE_VERSION: u8 = 1;
+pub const OWNER_STORE_VERSION: u8 = 1;
+// sizes of the accounts created before the layouts were versioned
+pub const LEGACY_HUB_SIZE: usize = 8 + 1 + 32;
+pub const LEGACY_BANK_SIZE: usize = 8 + 32 + 8 + 32 + 1;
+pub const LEGACY_X404_STATE_SIZE: usize = 8 + 32 + 8 + 8 + 32 + 1 + 32 + 32 + 32 + 8 + 8 + 8;
+pub const X404_STATE_SIZE: usize = 8
+    + 1
+    + 32
+    + 8
+    + 8
+    + 32
+    + 1
+    + 32
+    + 32
+    + 32
+    + 8
+    + 8
+    + 8
+    + 4
+    + MAX_URI_LENGTH
+    + 32
+    + 4
+    + MAX_URI_LENGTH
+    + 8
+    + 32
+    + 32
+    + 2
+    + 32
+    + 4
+    + 32 * MAX_MARKETPLACES
+    + 1
+    + 1;
 
 // validate incoming accounts here
 #[account]
@@ -18,6 +67,15 @@ pub struct X404Hub {
     pub emergency_close: bool,
     // manager who can create new X404 account or update underlying X404
     pub manager: Pubkey,
+    // hook programs approved for the fungible mints
+    pub hook_programs: Vec<Pubkey>,
+}
+
+impl X404Hub {
+    // account size needed for the current content
+    pub fn space(&self) -> usize {
+        8 + 1 + 32 + 4 + self.hook_programs.len() * 32
+    }
 }
 
 #[account]
@@ -30,12 +88,24 @@ pub struct NFTBank {
     pub owner: Pubkey,
     // If the corresponding tokens are issued
     pub issued: bool,
+    // issued NFT mirroring the metadata of this NFT, default if none
+    pub linked_nft: Pubkey,
 }
 
 #[account]
 pub struct OwnerStore {
-    // emergent close
+    // source of the state, must stay the first field so that the hook can
+    // derive the state from the store at a fixed offset
+    pub source: Pubkey,
+    // layout version, `OWNER_STORE_VERSION` once migrated
+    pub version: u8,
+    // owner -> unbound NFT mints assigned to the owner
     pub store: Vec<u8>,
+    // NFT mint -> current holder, reverse index of `store`
+    pub index: Vec<u8>,
+    // fungible token account -> owner and amount, the balance of an owner is
+    // summed over its token accounts
+    pub balances: Vec<u8>,
 }
 
 impl OwnerStore {
@@ -46,10 +116,157 @@ impl OwnerStore {
     pub fn update_map(&mut self, map: &HashMap<Pubkey, Vec<Pubkey>>) {
         self.store = map.try_to_vec().unwrap();
     }
+
+    pub fn get_index(&self) -> HashMap<Pubkey, Pubkey> {
+        HashMap::<Pubkey, Pubkey>::try_from_slice(self.index.as_slice()).unwrap()
+    }
+
+    pub fn update_index(&mut self, index: &HashMap<Pubkey, Pubkey>) {
+        self.index = index.try_to_vec().unwrap();
+    }
+
+    pub fn get_accounts(&self) -> HashMap<Pubkey, AccountBalance> {
+        HashMap::<Pubkey, AccountBalance>::try_from_slice(self.balances.as_slice()).unwrap()
+    }
+
+    pub fn update_accounts(&mut self, accounts: &HashMap<Pubkey, AccountBalance>) {
+        self.balances = accounts.try_to_vec().unwrap();
+    }
+
+    // owner -> fungible balance summed over all token accounts of the owner
+    pub fn get_balances(&self) -> HashMap<Pubkey, u64> {
+        owner_balances(&self.get_accounts())
+    }
+
+    // account size needed for the current content
+    pub fn space(&self) -> usize {
+        8 + 32 + 1 + 4 + self.store.len() + 4 + self.index.len() + 4 + self.balances.len()
+    }
+
+    // deserialize the maps once for an instruction, see `OwnerStoreMaps`
+    pub fn load(&self) -> OwnerStoreMaps {
+        let accounts = self.get_accounts();
+        OwnerStoreMaps {
+            map: self.get_map(),
+            index: self.get_index(),
+            balances: owner_balances(&accounts),
+            accounts,
+        }
+    }
+
+    // serialize the maps back, the account is resized by the caller
+    pub fn save(&mut self, maps: &OwnerStoreMaps) {
+        self.update_map(&maps.map);
+        self.update_index(&maps.index);
+        self.update_accounts(&maps.accounts);
+    }
+}
+
+// content of an `OwnerStore` loaded once per instruction, every change is
+// made on the maps and written back with a single `save`
+pub struct OwnerStoreMaps {
+    // owner -> unbound NFT mints assigned to the owner
+    pub map: HashMap<Pubkey, Vec<Pubkey>>,
+    // NFT mint -> current holder
+    pub index: HashMap<Pubkey, Pubkey>,
+    // fungible token account -> owner and amount
+    pub accounts: HashMap<Pubkey, AccountBalance>,
+    // owner -> balance summed over `accounts`, kept in step with it
+    pub balances: HashMap<Pubkey, u64>,
+}
+
+impl OwnerStoreMaps {
+    // NFT assigned to `owner`
+    pub fn assigned(&self, owner: &Pubkey) -> usize {
+        self.map.get(owner).map_or(0, |nfts| nfts.len())
+    }
+
+    // fungible balance of `owner` over all its token accounts
+    pub fn balance(&self, owner: &Pubkey) -> u64 {
+        self.balances.get(owner).copied().unwrap_or(0)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccountBalance {
+    // owner of the fungible token account
+    pub owner: Pubkey,
+    // amount of the fungible token account
+    pub amount: u64,
+}
+
+// owner -> fungible balance summed over the recorded token accounts
+pub fn owner_balances(accounts: &HashMap<Pubkey, AccountBalance>) -> HashMap<Pubkey, u64> {
+    let mut balances = HashMap::new();
+    for account in accounts.values() {
+        let balance: &mut u64 = balances.entry(account.owner).or_default();
+        *balance = balance.saturating_add(account.amount);
+    }
+    balances
+}
+
+#[account]
+pub struct ExemptionList {
+    // owners that never get NFTs assigned, e.g. AMM pools and vaults
+    pub owners: Vec<Pubkey>,
+}
+
+impl ExemptionList {
+    // account size needed for the current content
+    pub fn space(&self) -> usize {
+        8 + 4 + self.owners.len() * 32
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BoundNFT {
+    // issued NFT mint bound as a real token
+    pub mint: Pubkey,
+    // owner entitled to unbind the NFT, follows NFT transfers
+    pub holder: Pubkey,
+}
+
+#[account]
+pub struct BoundRegistry {
+    // NFTs currently bound, they stay counted in `nft_in_use`
+    pub nfts: Vec<BoundNFT>,
+}
+
+impl BoundRegistry {
+    // account size needed for the current content
+    pub fn space(&self) -> usize {
+        8 + 4 + self.nfts.len() * 64
+    }
+
+    // holder entitled to unbind `mint`, None if not bound
+    pub fn holder(&self, mint: &Pubkey) -> Option<Pubkey> {
+        self.nfts
+            .iter()
+            .find(|nft| nft.mint == *mint)
+            .map(|nft| nft.holder)
+    }
+}
+
+#[account]
+pub struct HolderConfig {
+    // holder only wants the fungible side, NFTs are kept in the pool
+    pub skip_nft: bool,
+}
+
+#[account]
+pub struct RerollRequest {
+    // holder who paid the reroll
+    pub holder: Pubkey,
+    // assigned NFT mint to exchange with the pool
+    pub nft_mint: Pubkey,
+    // slot of the request, its hash draws the pool mint
+    pub slot: u64,
 }
 
 #[account]
 pub struct X404State {
+    // layout version, `X404_STATE_VERSION` once migrated
+    pub version: u8,
     // liquidity source of X404
     pub source: Pubkey,
     // max waiting time for priority redeem
@@ -72,14 +289,102 @@ pub struct X404State {
     pub nft_in_use: u64,
     // fungible token per deposit/redeem
     pub fungible_supply: u64,
+    // uri of issued NFT until they are revealed
+    pub placeholder_uri: String,
+    // merkle root of the final uri of every issued NFT, zero without delayed reveal
+    pub reveal_hash: [u8; 32],
+    // base uri published by the reveal, empty before
+    pub base_uri: String,
+    // fungible token paid to the owner per reroll
+    pub reroll_fee: u64,
+    // randomness source of `settle_reroll`, the SlotHashes sysvar once rerolls
+    // are enabled, default while they are disabled
+    pub randomness: Pubkey,
+    // transfer hook program of the fungible and issued NFT mints
+    pub hook_program: Pubkey,
+    // creator royalty of issued NFT sales in basis points
+    pub royalty_bps: u16,
+    // receiver of the creator royalty
+    pub royalty_receiver: Pubkey,
+    // transfer authorities allowed to move issued NFT, anyone if empty
+    pub marketplaces: Vec<Pubkey>,
+    // bound NFT are frozen in the holder account until unbound
+    pub soulbound: bool,
+    // assigned NFT are mirrored as frozen tokens in the holder wallets
+    pub auto_bind: bool,
+}
+
+// X404State before the layout was versioned, read by `migrate`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyX404State {
+    pub source: Pubkey,
+    pub redeem_max_deadline: u64,
+    pub redeem_fee: u64,
+    pub owner: Pubkey,
+    pub decimal: u8,
+    pub fungible_mint: Pubkey,
+    pub fungible_hook: Pubkey,
+    pub collection_mint: Pubkey,
+    pub nft_supply: u64,
+    pub nft_in_use: u64,
+    pub fungible_supply: u64,
+}
+
+// OwnerStore before the layout was versioned, read by `migrate`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyOwnerStore {
+    pub store: Vec<u8>,
+}
+
+impl X404State {
+    pub fn delayed_reveal(&self) -> bool {
+        self.reveal_hash != [0; 32]
+    }
+
+    pub fn revealed(&self) -> bool {
+        !self.base_uri.is_empty()
+    }
+}
+
+// index of the pool mint drawn by `owner` rerolling `target`
+pub fn reroll_pick(randomness: [u8; 32], owner: Pubkey, target: Pubkey, pool: usize) -> usize {
+    let seed = hashv(&[randomness.as_ref(), owner.as_ref(), target.as_ref()]).to_bytes();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&seed[..8]);
+    (u64::from_le_bytes(bytes) % pool as u64) as usize
+}
+
+// domain prefixes of the reveal merkle tree, a leaf can never pass for a node
+const REVEAL_LEAF_PREFIX: &[u8] = &[0];
+const REVEAL_NODE_PREFIX: &[u8] = &[1];
+
+// leaf of the reveal merkle tree for the issued NFT `number`
+pub fn reveal_leaf(number: u64, path: &str) -> [u8; 32] {
+    hashv(&[
+        REVEAL_LEAF_PREFIX,
+        number.to_le_bytes().as_ref(),
+        path.as_bytes(),
+    ])
+    .to_bytes()
+}
+
+// parent of two nodes of the reveal merkle tree, hashed in sorted order so
+// proofs need no position
+pub fn reveal_parent(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[REVEAL_NODE_PREFIX, a.as_ref(), b.as_ref()]).to_bytes()
+    } else {
+        hashv(&[REVEAL_NODE_PREFIX, b.as_ref(), a.as_ref()]).to_bytes()
+    }
 }
+
 #[derive(Accounts)]
 #[instruction()]
 pub struct Initialize<'info> {
     #[account(
         init,
         payer = signer,
-        space = 8 + 32 + 1,
+        space = 8 + 32 + 1 + 4,
         seeds = [b"hub".as_ref()],
         bump,
     )]
@@ -89,6 +394,77 @@ pub struct Initialize<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(params:UpdateMetadataParams)]
+pub struct UpdateFungibleMetadata<'info> {
+    #[account(
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: Box<Account<'info, X404Hub>>,
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(mut,
+        seeds = [b"fungible_mint".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub fungible_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHookPrograms<'info> {
+    #[account(
+        mut,
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: Box<Account<'info, X404Hub>>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:MigrateParams)]
+pub struct Migrate<'info> {
+    // CHECK: hub in any layout, grown and checked in the instruction
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: UncheckedAccount<'info>,
+    // CHECK: state in any layout, rewritten in the instruction
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,
+    )]
+    pub state: UncheckedAccount<'info>,
+    // CHECK: owner store in any layout, rewritten in the instruction
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: UncheckedAccount<'info>,
+    // CHECK: fungible mint recorded in the state, its transfer hook names the
+    // hook program of a legacy state
+    pub fungible_mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 #[instruction(
     params: InitTokenParams
@@ -115,19 +491,15 @@ pub struct CreateX404<'info> {
         payer = signer,
         seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
         bump,
-        space = 8 + 4 + 4,
+        space = 8 + 32 + 1 + 4 + 4 + 4 + 4 + 4 + 4,
     )]
     pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: created together with its metadata in `mint_collection`
     #[account(
-        init,
-        payer = signer,
         seeds = [b"collection_mint".as_ref(), state.to_account_info().key.as_ref()],
         bump,
-        mint::decimals = 0,
-        mint::authority = state,
-        mint::token_program = token_program,
     )]
-    pub collection_mint: InterfaceAccount<'info, Mint>,
+    pub collection_mint: UncheckedAccount<'info>,
     // CHECK: there is no decent way to init token2022 with hook,
     // manually initiate it for now
     #[account(
@@ -135,9 +507,18 @@ pub struct CreateX404<'info> {
         bump,
     )]
     pub fungible_mint: UncheckedAccount<'info>,
+    // CHECK: hook program of the fungible mint
+    #[account(executable, address = params.hook_program)]
+    pub hook_program: UncheckedAccount<'info>,
+    // CHECK: extra account meta list of the fungible mint, verified to be the
+    // PDA of the hook program and initialized through CPI
+    #[account(mut, address = params.hook_extra_account)]
+    pub hook_extra_account: UncheckedAccount<'info>,
     #[account(mut, signer)]
     pub signer: Signer<'info>,
     pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub x404_program: Program<'info, X404>,
     pub system_program: Program<'info, System>,
 }
 
@@ -151,19 +532,17 @@ pub struct MintCollection<'info> {
         bump)
     ]
     pub state: Box<Account<'info, X404State>>,
+    // CHECK: the mint is created with metadata extensions, which anchor
+    // cannot init, so it is created manually
     #[account(mut,
         seeds = [b"collection_mint".as_ref(), state.to_account_info().key.as_ref()],
         bump,
     )]
-    pub collection_mint: InterfaceAccount<'info, Mint>,
-    #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = collection_mint,
-        associated_token::authority = collection_mint,
-        associated_token::token_program = token_program,
-    )]
-    pub collection_token: InterfaceAccount<'info, TokenAccount>,
+    pub collection_mint: UncheckedAccount<'info>,
+    // CHECK: associated token account of the collection mint owned by
+    // itself, address checked by the associated token program on creation
+    #[account(mut)]
+    pub collection_token: UncheckedAccount<'info>,
     #[account(mut, signer)]
     pub signer: Signer<'info>,
     pub token_program: Program<'info, Token2022>,
@@ -225,15 +604,41 @@ pub struct IssueTokens<'info> {
         bump,
     )]
     pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: exemption list of the state, may not be created yet
     #[account(
-        init_if_needed,
-        payer = owner,
+        seeds = [b"exemption".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub exemption: UncheckedAccount<'info>,
+    // CHECK: holder config of the user, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub user_config: UncheckedAccount<'info>,
+    // CHECK: collection of the issued NFTs, provides symbol and base uri and
+    // counts the new NFT as a member of its token group
+    #[account(
+        mut,
+        seeds = [b"collection_mint".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub collection_mint: UncheckedAccount<'info>,
+    // CHECK: next NFT mint, only created with its metadata when no issued
+    // NFT is free for reuse
+    #[account(
+        mut,
         seeds = [b"nft_mint".as_ref(),state.to_account_info().key.as_ref(), state.nft_supply.to_le_bytes().as_ref()],
         bump,
-        mint::decimals = 0,
-        mint::authority = state,
     )]
-    pub nft_mint: InterfaceAccount<'info, Mint>,
+    pub nft_mint: UncheckedAccount<'info>,
+    // CHECK: transfer hook program of the issued NFT mints
+    #[account(executable, address = state.hook_program)]
+    pub hook_program: UncheckedAccount<'info>,
+    // CHECK: extra account meta list of a new NFT mint, created by the hook
+    // program which checks its seeds
+    #[account(mut)]
+    pub nft_meta_list: UncheckedAccount<'info>,
     #[account(
         mut,
         seeds = [b"fungible_mint".as_ref(), state.to_account_info().key.as_ref()],
@@ -274,6 +679,13 @@ pub struct BindNFT<'info> {
         bump,
     )]
     pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: bound registry of the state, may not be created yet
+    #[account(
+        mut,
+        seeds = [b"bound".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub bound_registry: UncheckedAccount<'info>,
     #[account(
         mut,
         seeds = [b"nft_mint".as_ref(),state.to_account_info().key.as_ref(), params.number.to_le_bytes().as_ref()],
@@ -326,10 +738,30 @@ pub struct UnbindNFT<'info> {
         bump,
     )]
     pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: bound registry of the state, may not be created yet
+    #[account(
+        mut,
+        seeds = [b"bound".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub bound_registry: UncheckedAccount<'info>,
+    // CHECK: exemption list of the state, may not be created yet
+    #[account(
+        seeds = [b"exemption".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub exemption: UncheckedAccount<'info>,
+    // CHECK: holder config of the signer, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub signer_config: UncheckedAccount<'info>,
     #[account(
         mut,
         seeds = [b"nft_mint".as_ref(),state.to_account_info().key.as_ref(), params.number.to_le_bytes().as_ref()],
         bump,
+        constraint = unbind_mint.mint_authority == COption::Some(state.key()) @ SolX404Error::InvalidNFTMint,
     )]
     pub unbind_mint: InterfaceAccount<'info, Mint>,
     #[account(
@@ -360,47 +792,57 @@ pub struct UnbindNFT<'info> {
     pub system_program: Program<'info, System>,
 }
 
+// the NFT mints and their token accounts of the signer are passed in pairs
+// as remaining accounts, see `batch_nft_accounts`
 #[derive(Accounts)]
-#[instruction(params:RebalanceParams)]
-pub struct Rebalance<'info> {
+#[instruction(params:BatchBindParams)]
+pub struct BatchBindNFT<'info> {
+    #[account(mut,
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
     pub state: Box<Account<'info, X404State>>,
-    // CHECKED: account to store the NFT owned by the owner, should be owned by
-    // the program. Ideally should use init_if_needed, but because the store is
-    // dynamic size, haven't figured out how to init through constraint.
     #[account(
         mut,
         seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
         bump,
     )]
     pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: bound registry of the state, may not be created yet
     #[account(
-        seeds = [b"fungible_mint".as_ref(),state.to_account_info().key.as_ref()],
+        mut,
+        seeds = [b"bound".as_ref(), state.to_account_info().key.as_ref()],
         bump,
     )]
-    pub fungible_mint: InterfaceAccount<'info, Mint>,
+    pub bound_registry: UncheckedAccount<'info>,
     #[account(
-        associated_token::mint = fungible_mint,
-        associated_token::authority = params.sender,
-        associated_token::token_program = token_program,
+        mut,
+        seeds = [b"fungible_mint".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
     )]
-    pub sender_account: InterfaceAccount<'info, TokenAccount>,
+    pub fungible_mint: InterfaceAccount<'info, Mint>,
     #[account(
+        init_if_needed,
+        payer = signer,
         associated_token::mint = fungible_mint,
-        associated_token::authority = params.receiver,
+        associated_token::authority = signer,
         associated_token::token_program = token_program,
     )]
-    pub receiver_account: InterfaceAccount<'info, TokenAccount>,
-    pub hooker: AccountInfo<'info>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub fungible_token: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
     pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
+// the NFT mints and their token accounts of the signer are passed in pairs
+// as remaining accounts, see `batch_nft_accounts`
 #[derive(Accounts)]
-#[instruction(params:RedeemParams)]
-pub struct RedeemSPLNFT<'info> {
+#[instruction(params:BatchUnbindParams)]
+pub struct BatchUnbindNFT<'info> {
     #[account(mut,
         seeds = [b"state".as_ref(), params.source.as_ref()],
-        bump)]
+        bump,)]
     pub state: Box<Account<'info, X404State>>,
     #[account(
         mut,
@@ -408,51 +850,657 @@ pub struct RedeemSPLNFT<'info> {
         bump,
     )]
     pub owner_store: Box<Account<'info, OwnerStore>>,
-    #[account(mut)]
-    pub withdraw_mint: Box<Account<'info, SPLMint>>,
-    #[account(mut,
-        associated_token::mint = withdraw_mint,
-        associated_token::authority = state,
-        associated_token::token_program = withdrawal_program,)]
-    pub withdraw_holder: Box<Account<'info, SPLTokenAccount>>,
+    // CHECK: bound registry of the state, may not be created yet
     #[account(
-        init_if_needed,
-        payer = signer,
-        associated_token::mint = withdraw_mint,
-        associated_token::authority = signer,
-        associated_token::token_program = withdrawal_program,
+        mut,
+        seeds = [b"bound".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
     )]
-    pub withdrawal_receiver: Box<Account<'info, SPLTokenAccount>>,
-    #[account(mut,
-        seeds = [b"nft_bank".as_ref(), withdraw_mint.to_account_info().key.as_ref()],
+    pub bound_registry: UncheckedAccount<'info>,
+    // CHECK: exemption list of the state, may not be created yet
+    #[account(
+        seeds = [b"exemption".as_ref(), state.to_account_info().key.as_ref()],
         bump,
     )]
-    pub nft_bank: Box<Account<'info, NFTBank>>,
+    pub exemption: UncheckedAccount<'info>,
+    // CHECK: holder config of the signer, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub signer_config: UncheckedAccount<'info>,
     #[account(mut,
         seeds = [b"fungible_mint".as_ref(), state.to_account_info().key.as_ref()],
         bump,
     )]
-    pub fungible_mint: Box<InterfaceAccount<'info, Mint>>,
-    #[account(mut,
+    pub fungible_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = signer,
         associated_token::mint = fungible_mint,
         associated_token::authority = signer,
         associated_token::token_program = token_program,
     )]
-    pub fungible_token: Box<InterfaceAccount<'info, TokenAccount>>,
-    #[account(mut,
-        associated_token::mint = fungible_mint,
-        associated_token::authority = nft_bank.owner,
-        associated_token::token_program = token_program,
-    )]
-    pub original_owner: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub fungible_token: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
     pub signer: Signer<'info>,
-    pub withdrawal_program: Program<'info, Token>,
     pub token_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(params:RebalanceParams)]
+pub struct Rebalance<'info> {
+    pub state: Box<Account<'info, X404State>>,
+    // CHECKED: account to store the NFT owned by the owner, should be owned by
+    // the program. Ideally should use init_if_needed, but because the store is
+    // dynamic size, haven't figured out how to init through constraint.
+    #[account(
+        mut,
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: exemption list of the state, may not be created yet
+    #[account(
+        seeds = [b"exemption".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub exemption: UncheckedAccount<'info>,
+    // CHECK: holder config of the sender, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), params.sender.as_ref()],
+        bump,
+    )]
+    pub sender_config: UncheckedAccount<'info>,
+    // CHECK: holder config of the receiver, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), params.receiver.as_ref()],
+        bump,
+    )]
+    pub receiver_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"fungible_mint".as_ref(),state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub fungible_mint: InterfaceAccount<'info, Mint>,
+    // any token account of the fungible mint, NFTs are accounted per owner
+    #[account(
+        token::mint = fungible_mint,
+        token::authority = params.sender,
+        token::token_program = token_program,
+    )]
+    pub sender_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        token::mint = fungible_mint,
+        token::authority = params.receiver,
+        token::token_program = token_program,
+    )]
+    pub receiver_account: InterfaceAccount<'info, TokenAccount>,
+    pub hooker: AccountInfo<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct TransferBound<'info> {
+    pub state: Box<Account<'info, X404State>>,
+    // CHECK: bound registry of the state, created by the first bind
+    #[account(
+        mut,
+        seeds = [b"bound".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub bound_registry: UncheckedAccount<'info>,
+    #[account(constraint = nft_mint.mint_authority == COption::Some(state.key()))]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+    #[account(token::mint = nft_mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    #[account(token::mint = nft_mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    // meta list of the NFT mint, signed by the hook program
+    pub hooker: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:RedeemParams)]
+pub struct RedeemSPLNFT<'info> {
+    #[account(mut,
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        mut,
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: exemption list of the state, may not be created yet
+    #[account(
+        seeds = [b"exemption".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub exemption: UncheckedAccount<'info>,
+    // CHECK: holder config of the signer, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub signer_config: UncheckedAccount<'info>,
+    // CHECK: holder config of the original owner, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), nft_bank.owner.as_ref()],
+        bump,
+    )]
+    pub original_config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub withdraw_mint: Box<Account<'info, SPLMint>>,
+    #[account(mut,
+        associated_token::mint = withdraw_mint,
+        associated_token::authority = state,
+        associated_token::token_program = withdrawal_program,)]
+    pub withdraw_holder: Box<Account<'info, SPLTokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = withdraw_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = withdrawal_program,
+    )]
+    pub withdrawal_receiver: Box<Account<'info, SPLTokenAccount>>,
+    #[account(mut,
+        seeds = [b"nft_bank".as_ref(), withdraw_mint.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub nft_bank: Box<Account<'info, NFTBank>>,
+    #[account(mut,
+        seeds = [b"fungible_mint".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub fungible_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut,
+        associated_token::mint = fungible_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+    pub fungible_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut,
+        associated_token::mint = fungible_mint,
+        associated_token::authority = nft_bank.owner,
+        associated_token::token_program = token_program,
+    )]
+    pub original_owner: Box<InterfaceAccount<'info, TokenAccount>>,
+    // CHECK: issued NFT linked to the deposit, required when the bank has one
+    #[account(mut)]
+    pub linked_nft: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub withdrawal_program: Program<'info, Token>,
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:SetRevealParams)]
+pub struct SetReveal<'info> {
+    #[account(
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: Box<Account<'info, X404Hub>>,
+    #[account(mut,
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:RevealParams)]
+pub struct Reveal<'info> {
+    #[account(
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: Box<Account<'info, X404Hub>>,
+    #[account(mut,
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:SetRerollParams)]
+pub struct SetReroll<'info> {
+    #[account(
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: Box<Account<'info, X404Hub>>,
+    #[account(mut,
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:AutoBindParams)]
+pub struct SetAutoBind<'info> {
+    #[account(
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: Box<Account<'info, X404Hub>>,
+    #[account(mut,
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    pub signer: Signer<'info>,
+}
+
+// the NFT mints and token accounts to reconcile are passed in pairs as
+// remaining accounts, see `auto_bind`
+#[derive(Accounts)]
+#[instruction(params:SyncAutoBindParams)]
+pub struct SyncAutoBind<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:SoulboundParams)]
+pub struct SetSoulbound<'info> {
+    #[account(
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: Box<Account<'info, X404Hub>>,
+    #[account(mut,
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:RoyaltyParams)]
+pub struct SetRoyalty<'info> {
+    #[account(
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: Box<Account<'info, X404Hub>>,
+    #[account(mut,
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    // CHECK: collection of the issued NFTs, publishes the royalty in its metadata
+    #[account(
+        mut,
+        seeds = [b"collection_mint".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub collection_mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:RerollParams)]
+pub struct Reroll<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        mut,
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: exemption list of the state, may not be created yet
+    #[account(
+        seeds = [b"exemption".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub exemption: UncheckedAccount<'info>,
+    // CHECK: holder config of the signer, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub signer_config: UncheckedAccount<'info>,
+    // CHECK: holder config of the state owner, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), state.owner.as_ref()],
+        bump,
+    )]
+    pub owner_config: UncheckedAccount<'info>,
+    #[account(mut,
+        seeds = [b"fungible_mint".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub fungible_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut,
+        associated_token::mint = fungible_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program,
+    )]
+    pub fungible_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = fungible_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub fee_receiver: Box<InterfaceAccount<'info, TokenAccount>>,
+    // CHECK: owner of the state receiving the fee
+    #[account(address = state.owner)]
+    pub owner: UncheckedAccount<'info>,
+    // one pending request per holder, settled in a later slot
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"reroll".as_ref(), state.to_account_info().key.as_ref(), signer.key().as_ref()],
+        bump,
+        space = REROLL_REQUEST_SIZE,
+    )]
+    pub request: Box<Account<'info, RerollRequest>>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:SettleRerollParams)]
+pub struct SettleReroll<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        mut,
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    #[account(
+        mut,
+        close = holder,
+        seeds = [b"reroll".as_ref(), state.to_account_info().key.as_ref(), params.holder.as_ref()],
+        bump,
+    )]
+    pub request: Box<Account<'info, RerollRequest>>,
+    // CHECK: holder of the request, gets its rent back
+    #[account(mut, address = params.holder)]
+    pub holder: UncheckedAccount<'info>,
+    // CHECK: the SlotHashes sysvar, read in the instruction
+    #[account(address = state.randomness)]
+    pub randomness: UncheckedAccount<'info>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:RevealNFTParams)]
+pub struct RevealNFT<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    // CHECK: issued NFT mint to reveal, created by `issue_token`
+    #[account(
+        mut,
+        seeds = [b"nft_mint".as_ref(), state.to_account_info().key.as_ref(), params.number.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub nft_mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:LinkParams)]
+pub struct LinkDeposit<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(mut,
+        seeds = [b"nft_bank".as_ref(), deposit_mint.to_account_info().key.as_ref()],
+        bump,
+        constraint = nft_bank.issued @ SolX404Error::DepositNotIssued,
+    )]
+    pub nft_bank: Box<Account<'info, NFTBank>>,
+    pub deposit_mint: Box<Account<'info, SPLMint>>,
+    // banks are keyed by the deposit only, holding the deposit ties the bank to
+    // this state
+    #[account(
+        associated_token::mint = deposit_mint,
+        associated_token::authority = state,
+        constraint = deposit_receiver.amount == 1 @ SolX404Error::DepositNotIssued,
+    )]
+    pub deposit_receiver: Box<Account<'info, SPLTokenAccount>>,
+    // CHECK: metaplex metadata of the deposited NFT, parsed in the instruction
+    #[account(
+        seeds = [b"metadata".as_ref(), TOKEN_METADATA_PROGRAM_ID.as_ref(), deposit_mint.to_account_info().key.as_ref()],
+        bump,
+        seeds::program = TOKEN_METADATA_PROGRAM_ID,
+    )]
+    pub deposit_metadata: UncheckedAccount<'info>,
+    // CHECK: issued NFT mint to link, created by `issue_token`
+    #[account(
+        mut,
+        seeds = [b"nft_mint".as_ref(), state.to_account_info().key.as_ref(), params.number.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub nft_mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:ExemptionParams)]
+pub struct UpdateExemption<'info> {
+    #[account(
+        seeds = [b"hub".as_ref()],
+        bump,
+    )]
+    pub hub: Box<Account<'info, X404Hub>>,
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        mut,
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: created by the first exemption, resized on every update
+    #[account(
+        mut,
+        seeds = [b"exemption".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub exemption: UncheckedAccount<'info>,
+    // CHECK: holder config of the owner, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), params.owner.as_ref()],
+        bump,
+    )]
+    pub holder_config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:SkipNFTParams)]
+pub struct SetSkipNFT<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        mut,
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: exemption list of the state, may not be created yet
+    #[account(
+        seeds = [b"exemption".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub exemption: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), signer.key().as_ref()],
+        bump,
+        space = HOLDER_CONFIG_SIZE,
+    )]
+    pub holder_config: Box<Account<'info, HolderConfig>>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:SyncBalanceParams)]
+pub struct SyncBalance<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        mut,
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: exemption list of the state, may not be created yet
+    #[account(
+        seeds = [b"exemption".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub exemption: UncheckedAccount<'info>,
+    // CHECK: holder config of the token account owner, may not be created yet
+    #[account(
+        seeds = [b"holder".as_ref(), state.to_account_info().key.as_ref(), token_account.owner.as_ref()],
+        bump,
+    )]
+    pub holder_config: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"fungible_mint".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub fungible_mint: Box<InterfaceAccount<'info, Mint>>,
+    // any token account of the fungible mint
+    #[account(token::mint = fungible_mint)]
+    pub token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    // pays the rent of the owner store when the synced record makes it grow
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:ViewOwnerParams)]
+pub struct ViewOwnerNFTs<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:ViewHolderParams)]
+pub struct ViewNFTHolder<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: only the address is used, the mint may be bound or not created yet
+    #[account(
+        seeds = [b"nft_mint".as_ref(),state.to_account_info().key.as_ref(), params.number.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub nft_mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:ViewStateParams)]
+pub struct ViewState<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:ViewStateParams)]
+pub struct AuditState<'info> {
+    #[account(
+        seeds = [b"state".as_ref(), params.source.as_ref()],
+        bump,)]
+    pub state: Box<Account<'info, X404State>>,
+    #[account(
+        seeds = [b"owner_store".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub owner_store: Box<Account<'info, OwnerStore>>,
+    // CHECK: bound registry of the state, may not be created yet
+    #[account(
+        seeds = [b"bound".as_ref(), state.to_account_info().key.as_ref()],
+        bump,
+    )]
+    pub bound_registry: UncheckedAccount<'info>,
+    #[account(address = state.fungible_mint)]
+    pub fungible_mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+#[instruction(params:ViewBankParams)]
+pub struct ViewBank<'info> {
+    #[account(
+        seeds = [b"nft_bank".as_ref(), params.id.as_ref()],
+        bump,
+    )]
+    pub nft_bank: Box<Account<'info, NFTBank>>,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
 pub struct InitTokenParams {
     // max waiting time for priority redeem
@@ -467,6 +1515,12 @@ pub struct InitTokenParams {
     pub hook_extra_account: Pubkey,
     // hook program id
     pub hook_program: Pubkey,
+    // name of the fungible token
+    pub name: String,
+    // symbol of the fungible token
+    pub symbol: String,
+    // uri of the fungible token metadata
+    pub uri: String,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
@@ -475,26 +1529,14 @@ pub struct InitCollectionParams {
     pub name: String,
     // symbol for NFT token
     pub symbol: String,
-    // uri for fungible token
+    // uri for the collection metadata
     pub uri: String,
+    // maximum number of NFTs the collection group can ever hold
+    pub max_size: u32,
     // pubkey of source
     pub source: Pubkey,
 }
 
-// impl InitCollectionParams {
-//     pub fn data(&self) -> DataV2 {
-//         DataV2 {
-//             name: self.name.clone(),
-//             symbol: self.symbol.clone(),
-//             uri: self.uri.clone(),
-//             seller_fee_basis_points: 5,
-//             creators: None,
-//             collection: None,
-//             uses: None,
-//         }
-//     }
-// }
-
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
 pub struct DepositParams {
     //pubkey of source
@@ -525,12 +1567,126 @@ pub struct UnbindParams {
     pub number: u64,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct BatchBindParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // numbers of the NFTs to bind
+    pub numbers: Vec<u64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct BatchUnbindParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // numbers of the NFTs to unbind
+    pub numbers: Vec<u64>,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
 pub struct RedeemParams {
     // pubkey of source
     pub source: Pubkey,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct SetRevealParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // uri of issued NFT until the reveal
+    pub placeholder_uri: String,
+    // merkle root of hash(number, path) for every issued NFT
+    pub reveal_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct RevealParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // base uri of the final metadata
+    pub base_uri: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct RevealNFTParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // number of the issued NFT to reveal
+    pub number: u64,
+    // final uri of the NFT relative to the base uri
+    pub path: String,
+    // merkle proof of (number, path) against the reveal hash
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct SetRerollParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // fungible token paid to the owner per reroll
+    pub reroll_fee: u64,
+    // randomness source, only the SlotHashes sysvar is supported
+    pub randomness: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct SoulboundParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // freeze NFT bound from now on
+    pub soulbound: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct AutoBindParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // mirror assigned NFT in the holder wallets
+    pub auto_bind: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct SyncAutoBindParams {
+    // pubkey of source
+    pub source: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct RoyaltyParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // creator royalty in basis points
+    pub royalty_bps: u16,
+    // receiver of the creator royalty
+    pub royalty_receiver: Pubkey,
+    // transfer authorities allowed to move issued NFT, anyone if empty
+    pub marketplaces: Vec<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct RerollParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // assigned NFT mint to exchange with the pool
+    pub nft_mint: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct SettleRerollParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // holder whose pending reroll is settled
+    pub holder: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct LinkParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // number of the issued NFT to link
+    pub number: u64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
 pub struct RebalanceParams {
     // pubkey of sender
@@ -540,3 +1696,172 @@ pub struct RebalanceParams {
     // amount to rebalance
     pub amount: u64,
 }
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct UpdateMetadataParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // name of the fungible token
+    pub name: String,
+    // symbol of the fungible token
+    pub symbol: String,
+    // uri of the fungible token metadata
+    pub uri: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct HookProgramParams {
+    // hook program to approve or revoke
+    pub program: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ExemptionParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // owner to exempt from NFT assignment
+    pub owner: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct SkipNFTParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // true to only hold the fungible side
+    pub skip: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct MigrateParams {
+    // pubkey of source
+    pub source: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct SyncBalanceParams {
+    // pubkey of source
+    pub source: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ViewOwnerParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // owner to query
+    pub owner: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ViewHolderParams {
+    // pubkey of source
+    pub source: Pubkey,
+    // issued NFT number
+    pub number: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ViewStateParams {
+    // pubkey of source
+    pub source: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ViewBankParams {
+    // mint of the deposited NFT
+    pub id: Pubkey,
+}
+
+// Return data of the view instructions. These are decoupled from the
+// account layouts, so new fields must only be appended to keep old
+// clients decoding correctly.
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OwnerNFTsView {
+    // owner queried
+    pub owner: Pubkey,
+    // unbound NFT mints assigned to the owner
+    pub nfts: Vec<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NFTHolderView {
+    // issued NFT mint
+    pub mint: Pubkey,
+    // current holder in owner store, None if bound or not issued
+    pub holder: Option<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StateSummaryView {
+    // liquidity source of X404
+    pub source: Pubkey,
+    // Hub Pubkey for this X404
+    pub owner: Pubkey,
+    // fungible mint for this X404
+    pub fungible_mint: Pubkey,
+    // nft mint for this X404
+    pub collection_mint: Pubkey,
+    // decimal for fungible token
+    pub decimal: u8,
+    // fungible token per deposit/redeem
+    pub fungible_supply: u64,
+    // redeem fee for x404
+    pub redeem_fee: u64,
+    // max waiting time for priority redeem
+    pub redeem_max_deadline: u64,
+    // supply of nft
+    pub nft_supply: u64,
+    // nft backed by deposits
+    pub nft_in_use: u64,
+    // whether the base uri of a delayed reveal is published
+    pub revealed: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum AuditDiscrepancy {
+    // NFT in owner store and bound registry differ from `nft_supply`
+    NFTSupply { expected: u64, actual: u64 },
+    // `nft_in_use` above `nft_supply`
+    NFTInUse { nft_in_use: u64, nft_supply: u64 },
+    // fungible mint supply differs from the NFT in use and not bound
+    FungibleSupply { expected: u64, actual: u64 },
+    // owner store balances differ from the fungible mint supply
+    OwnerStoreBalance { expected: u64, actual: u64 },
+    // reverse index of the owner store differs from the assignments
+    OwnerStoreIndex { expected: u64, actual: u64 },
+    // owners assigned more NFT than their balance allows, logged one by one
+    OverAssigned { owners: u64 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuditView {
+    // NFT mints created
+    pub nft_supply: u64,
+    // NFT backed by deposits
+    pub nft_in_use: u64,
+    // supply of the fungible mint
+    pub fungible_mint_supply: u64,
+    // sum of the owner store balances
+    pub balances: u64,
+    // NFT assigned in the owner store, the pool included
+    pub assigned: u64,
+    // NFT in the pool of the state
+    pub pool: u64,
+    // NFT in the bound registry
+    pub bound: u64,
+    // empty when the accounting is coherent
+    pub discrepancies: Vec<AuditDiscrepancy>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BankInfoView {
+    // mint of the deposited NFT
+    pub id: Pubkey,
+    // owner of this NFT
+    pub owner: Pubkey,
+    // deadline for redeem
+    pub redeem_deadline: u64,
+    // If the corresponding tokens are issued
+    pub issued: bool,
+    // issued NFT mirroring the metadata of this NFT, default if none
+    pub linked_nft: Pubkey,
+}
@@ -1,22 +1,69 @@
-use std::cmp::min;
+use std::{cmp::min, collections::HashMap};
 
 use anchor_lang::{
     context::CpiContext,
     prelude::*,
-    system_program::{create_account, CreateAccount},
+    solana_program::{
+        instruction::Instruction,
+        program::{invoke, invoke_signed},
+        sysvar::slot_hashes,
+    },
+    system_program::{create_account, transfer, CreateAccount, Transfer},
+    Discriminator,
 };
 use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
     token::{
         close_account as spl_close_account, transfer_checked as spl_transfer_checked,
         CloseAccount as CloseSPLAccount, TransferChecked as SPLTransferChecked,
     },
     token_2022::{
-        burn, close_account, initialize_mint2, mint_to, Burn, CloseAccount, InitializeMint2, MintTo,
+        burn, close_account, freeze_account, initialize_mint2, mint_to, thaw_account, Burn,
+        CloseAccount, FreezeAccount, InitializeMint2, MintTo, ThawAccount, Token2022,
     },
     token_interface::Mint,
 };
+// token2022 1.0 knows the token group extensions, anchor-spl still uses 0.9
+use spl_token_2022::{
+    extension::{
+        group_member_pointer::instruction::initialize as group_member_pointer_initialize,
+        group_pointer::instruction::initialize as group_pointer_initialize,
+        metadata_pointer::instruction::initialize as metadata_pointer_initialize,
+        permanent_delegate::PermanentDelegate,
+        transfer_hook::{instruction::initialize as hook_initialize, TransferHook},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    instruction::initialize_permanent_delegate,
+    state::{Account as SPL2022Account, Mint as SPL2022Mint},
+};
+use spl_token_group_interface::instruction::{initialize_group, initialize_member};
+use spl_token_metadata_interface::{
+    instruction::{initialize as metadata_initialize, remove_key, update_field},
+    state::{Field, TokenMetadata},
+};
 
-use crate::{error::SolX404Error, OwnerStore};
+use crate::{
+    error::SolX404Error, reroll_pick, reveal_parent, AccountBalance, BoundNFT, BoundRegistry,
+    ExemptionList, HolderConfig, LegacyOwnerStore, LegacyX404State, OwnerStore, OwnerStoreMaps,
+    X404State, LEGACY_X404_STATE_SIZE, MAX_AUTO_BIND_PER_CALL, OWNER_STORE_VERSION,
+    TOKEN_METADATA_PROGRAM_ID, X404_STATE_SIZE, X404_STATE_VERSION,
+};
+
+// metadata keys recording the deposit mirrored by an issued NFT
+const LINKED_DEPOSIT_KEY: &str = "deposit";
+const DEPOSIT_NAME_KEY: &str = "deposit_name";
+const DEPOSIT_SYMBOL_KEY: &str = "deposit_symbol";
+const ORIGINAL_URI_KEY: &str = "x404_uri";
+// metadata keys publishing the creator royalty on the collection mint
+const ROYALTY_BPS_KEY: &str = "royalty_bps";
+const ROYALTY_RECEIVER_KEY: &str = "royalty_receiver";
+
+// on-chain fields of the metaplex metadata of a deposited NFT
+pub(crate) struct DepositMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
 
 pub(crate) fn mint_nft<'info>(
     token_program: AccountInfo<'info>,
@@ -62,6 +109,39 @@ pub(crate) fn mint_token<'info>(
     mint_to(cpi_context, amount)
 }
 
+// freeze or thaw a bound NFT account, the state is the freeze authority of
+// issued NFT mints
+pub(crate) fn set_frozen<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    frozen: bool,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    if frozen {
+        freeze_account(CpiContext::new_with_signer(
+            token_program,
+            FreezeAccount {
+                account,
+                mint,
+                authority: state,
+            },
+            state_signer,
+        ))
+    } else {
+        thaw_account(CpiContext::new_with_signer(
+            token_program,
+            ThawAccount {
+                account,
+                mint,
+                authority: state,
+            },
+            state_signer,
+        ))
+    }
+}
+
 pub(crate) fn burn_token<'info>(
     token_program: AccountInfo<'info>,
     mint: AccountInfo<'info>,
@@ -81,6 +161,7 @@ pub(crate) fn burn_token<'info>(
     burn(cpi_context, amount)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn transfer_spl_token<'info>(
     token_program: AccountInfo<'info>,
     mint: AccountInfo<'info>,
@@ -144,59 +225,1449 @@ pub(crate) fn close_token_account<'info>(
 }
 
 pub(crate) fn add_to_owner_store(
-    account: &mut Account<'_, OwnerStore>,
+    store: &mut OwnerStoreMaps,
     target: Pubkey,
     owner: Pubkey,
 ) -> Result<()> {
-    let mut map = account.get_map();
-    map.entry(owner).or_insert_with(Vec::new).push(target);
-    // add rent,
-    let new_len = map.try_to_vec()?.len() + 4 + 8;
-    account.to_account_info().realloc(new_len, false)?;
-    account.update_map(&map);
+    add_many_to_owner_store(store, &[target], owner)
+}
+
+// add all `targets` to `owner`
+pub(crate) fn add_many_to_owner_store(
+    store: &mut OwnerStoreMaps,
+    targets: &[Pubkey],
+    owner: Pubkey,
+) -> Result<()> {
+    store
+        .map
+        .entry(owner)
+        .or_default()
+        .extend_from_slice(targets);
+    for target in targets {
+        store.index.insert(*target, owner);
+    }
 
     Ok(())
 }
 
 pub(crate) fn transfer_from_owner_store(
-    account: &mut Account<'_, OwnerStore>,
+    store: &mut OwnerStoreMaps,
     owner: Pubkey,
     to: Pubkey,
     amount: usize,
 ) -> Result<()> {
-    if amount == 0 {
+    if amount == 0 || owner == to {
         return Ok(());
     }
 
-    let mut map = account.get_map();
-    let record = map.entry(owner).or_insert_with(Vec::new);
+    let record = store.map.entry(owner).or_default();
     require!(record.len() >= amount, SolX404Error::InsufficientNFT);
 
     let mut tail = record.split_off(record.len() - amount);
-    let target = map.entry(to).or_insert_with(Vec::new);
-    target.append(&mut tail);
-
-    let new_len = map.try_to_vec()?.len() + 4 + 8;
-    account.to_account_info().realloc(new_len, false)?;
-    account.update_map(&map);
+    if record.is_empty() {
+        store.map.remove(&owner);
+    }
+    for mint in tail.iter() {
+        store.index.insert(*mint, to);
+    }
+    store.map.entry(to).or_default().append(&mut tail);
 
     Ok(())
 }
 
 pub(crate) fn take_from_owner_store(
-    account: &mut Account<'_, OwnerStore>,
+    store: &mut OwnerStoreMaps,
+    owner: Pubkey,
+    target: Pubkey,
+) -> Result<()> {
+    take_many_from_owner_store(store, owner, &[target])
+}
+
+// take all `targets` from `owner`
+pub(crate) fn take_many_from_owner_store(
+    store: &mut OwnerStoreMaps,
+    owner: Pubkey,
+    targets: &[Pubkey],
+) -> Result<()> {
+    let record = store.map.entry(owner).or_default();
+
+    for target in targets {
+        require!(record.contains(target), SolX404Error::InsufficientNFT);
+
+        record.retain(|x| x != target);
+        store.index.remove(target);
+    }
+    if record.is_empty() {
+        store.map.remove(&owner);
+    }
+
+    Ok(())
+}
+
+// move `amount` into the summed balance of `owner`
+fn credit_balance(store: &mut OwnerStoreMaps, owner: Pubkey, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let balance = store.balances.entry(owner).or_default();
+    *balance = balance
+        .checked_add(amount)
+        .ok_or(SolX404Error::ArithmeticOverflow)?;
+    Ok(())
+}
+
+// move `amount` out of the summed balance of `owner`
+fn debit_balance(store: &mut OwnerStoreMaps, owner: Pubkey, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let balance = store.balances.entry(owner).or_default();
+    *balance = balance
+        .checked_sub(amount)
+        .ok_or(SolX404Error::ArithmeticUnderflow)?;
+    if *balance == 0 {
+        store.balances.remove(&owner);
+    }
+    Ok(())
+}
+
+// record `amount` fungible tokens received by `token_account` of `owner`,
+// returns the balance of the owner before
+pub(crate) fn increase_balance(
+    store: &mut OwnerStoreMaps,
+    token_account: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> Result<u64> {
+    let before = store.balance(&owner);
+    let record = store
+        .accounts
+        .entry(token_account)
+        .or_insert(AccountBalance { owner, amount: 0 });
+    require_keys_eq!(record.owner, owner, SolX404Error::BalanceOutOfSync);
+    record.amount = record
+        .amount
+        .checked_add(amount)
+        .ok_or(SolX404Error::ArithmeticOverflow)?;
+    credit_balance(store, owner, amount)?;
+
+    Ok(before)
+}
+
+// record `amount` fungible tokens sent by `token_account` of `owner`, returns
+// the balance of the owner before
+pub(crate) fn decrease_balance(
+    store: &mut OwnerStoreMaps,
+    token_account: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> Result<u64> {
+    let before = store.balance(&owner);
+    let record = store
+        .accounts
+        .entry(token_account)
+        .or_insert(AccountBalance { owner, amount: 0 });
+    require_keys_eq!(record.owner, owner, SolX404Error::BalanceOutOfSync);
+    record.amount = record
+        .amount
+        .checked_sub(amount)
+        .ok_or(SolX404Error::ArithmeticUnderflow)?;
+
+    if record.amount == 0 {
+        store.accounts.remove(&token_account);
+    }
+    debit_balance(store, owner, amount)?;
+
+    Ok(before)
+}
+
+// bring the record of `token_account` to its live `owner` and `amount`. Direct
+// burns and owner changes never reach the hook, so the record drifts until
+// the account is synced. Returns the owners whose balance changed.
+pub(crate) fn sync_token_account(
+    store: &mut OwnerStoreMaps,
+    token_account: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> Result<Vec<Pubkey>> {
+    let live = AccountBalance { owner, amount };
+    let recorded = store
+        .accounts
+        .get(&token_account)
+        .copied()
+        .unwrap_or(AccountBalance { owner, amount: 0 });
+    if recorded == live {
+        return Ok(Vec::new());
+    }
+
+    msg!(
+        "{} synced from {} of {} to {} of {}",
+        token_account,
+        recorded.amount,
+        recorded.owner,
+        amount,
+        owner
+    );
+    if amount == 0 {
+        store.accounts.remove(&token_account);
+    } else {
+        store.accounts.insert(token_account, live);
+    }
+    debit_balance(store, recorded.owner, recorded.amount)?;
+    credit_balance(store, owner, amount)?;
+
+    if recorded.owner == owner {
+        Ok(vec![owner])
+    } else {
+        Ok(vec![recorded.owner, owner])
+    }
+}
+
+// return the NFTs `owner` is no longer entitled to after a sync to the pool of
+// `state`, owners holding no NFT (e.g. exempted) are left untouched
+pub(crate) fn release_surplus(
+    store: &mut OwnerStoreMaps,
+    state: Pubkey,
+    owner: Pubkey,
+    fungible_supply: u64,
+) -> Result<()> {
+    let surplus = store
+        .assigned(&owner)
+        .saturating_sub(nft_units(store.balance(&owner), fungible_supply)?);
+
+    msg!("{} release {} surplus to pool", owner, surplus);
+    transfer_from_owner_store(store, owner, state, surplus)
+}
+
+// give back the NFTs `owner` is entitled to from the pool of `state`, up to
+// `balance / fungible_supply` and as many as the pool holds
+pub(crate) fn claim_from_pool(
+    store: &mut OwnerStoreMaps,
+    state: Pubkey,
+    owner: Pubkey,
+    fungible_supply: u64,
+) -> Result<()> {
+    let entitled = nft_units(store.balance(&owner), fungible_supply)?;
+    let amount = min(
+        entitled.saturating_sub(store.assigned(&owner)),
+        store.assigned(&state),
+    );
+
+    msg!("{} claim {} from pool", owner, amount);
+    transfer_from_owner_store(store, state, owner, amount)
+}
+
+// exchange `target` assigned to `owner` with the pool mint at `pick` modulo
+// the pool size, returns the drawn mint
+pub(crate) fn swap_with_pool(
+    store: &mut OwnerStoreMaps,
+    state: Pubkey,
+    owner: Pubkey,
+    target: Pubkey,
+    randomness: [u8; 32],
+) -> Result<Pubkey> {
+    let position = store
+        .map
+        .get(&owner)
+        .and_then(|record| record.iter().position(|mint| mint == &target))
+        .ok_or(SolX404Error::NotNFTHolder)?;
+
+    let pool = store.map.entry(state).or_default();
+    require!(!pool.is_empty(), SolX404Error::EmptyPool);
+    let pick = reroll_pick(randomness, owner, target, pool.len());
+    let drawn = std::mem::replace(&mut pool[pick], target);
+    store.map.entry(owner).or_default()[position] = drawn;
+
+    store.index.insert(target, state);
+    store.index.insert(drawn, owner);
+
+    Ok(drawn)
+}
+
+// write `store` back into `account` and fit the account to it. The hook
+// invoked `rebalance` has no payer, so a store that grows is paid from its
+// own spare lamports, topped up by `payer` (with the system program) when the
+// instruction has one, and fails with `OwnerStoreUnderfunded` otherwise. A
+// store that shrinks keeps its lamports as reserve for later growth.
+pub(crate) fn save_owner_store<'info>(
+    account: &mut Account<'info, OwnerStore>,
+    store: &OwnerStoreMaps,
+    payer: Option<(AccountInfo<'info>, AccountInfo<'info>)>,
+) -> Result<()> {
+    account.save(store);
+    let space = account.space();
+    let info = account.to_account_info();
+    if space > info.data_len() {
+        if let Some((payer, system_program)) = payer {
+            top_up_rent(info.clone(), payer, system_program, space)?;
+        }
+        let required = Rent::get()?.minimum_balance(space);
+        if info.lamports() < required {
+            msg!(
+                "owner store needs {} more lamports to grow to {} bytes",
+                required - info.lamports(),
+                space
+            );
+            return err!(SolX404Error::OwnerStoreUnderfunded);
+        }
+    }
+    if space != info.data_len() {
+        info.realloc(space, false)?;
+    }
+    Ok(())
+}
+
+// hash of `slot` in the SlotHashes sysvar, None once it left the recent
+// slots. The entries are (slot, hash) pairs after a u64 count, newest first,
+// read in place as the sysvar is too large to deserialize.
+pub(crate) fn recent_slot_hash(slot_hashes: &AccountInfo, slot: u64) -> Result<Option<[u8; 32]>> {
+    require_keys_eq!(
+        slot_hashes.key(),
+        slot_hashes::ID,
+        SolX404Error::InvalidRandomness
+    );
+
+    let data = slot_hashes.try_borrow_data()?;
+    require!(data.len() >= 8, SolX404Error::InvalidRandomness);
+    let count = u64::from_le_bytes(data[..8].try_into().unwrap()) as usize;
+    for entry in data[8..].chunks_exact(40).take(count) {
+        let entry_slot = u64::from_le_bytes(entry[..8].try_into().unwrap());
+        if entry_slot == slot {
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(&entry[8..]);
+            return Ok(Some(hash));
+        }
+        if entry_slot < slot {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+// the only reroll source: the hash of the request slot, unknown when the
+// request is sent and read by `settle_reroll` in a later slot
+pub(crate) fn valid_randomness(randomness: Pubkey) -> bool {
+    randomness == slot_hashes::ID
+}
+
+// `owner` still holds `target` and the pool has a mint to draw
+pub(crate) fn can_reroll(
+    store: &OwnerStoreMaps,
+    state: Pubkey,
     owner: Pubkey,
     target: Pubkey,
+) -> bool {
+    store
+        .map
+        .get(&owner)
+        .is_some_and(|record| record.contains(&target))
+        && store.map.get(&state).is_some_and(|pool| !pool.is_empty())
+}
+
+// return all NFTs assigned to `owner` to the pool of `state`
+pub(crate) fn release_to_pool(
+    store: &mut OwnerStoreMaps,
+    state: Pubkey,
+    owner: Pubkey,
+) -> Result<()> {
+    let assigned = store.assigned(&owner);
+
+    msg!("{} release {} to pool", owner, assigned);
+    transfer_from_owner_store(store, owner, state, assigned)
+}
+
+// exemption list of a state, the account is only created by the first exemption
+pub(crate) fn load_exemptions(account: &AccountInfo) -> Result<Vec<Pubkey>> {
+    if account.data_is_empty() {
+        return Ok(Vec::new());
+    }
+
+    require_keys_eq!(
+        *account.owner,
+        crate::ID,
+        SolX404Error::InvalidExemptionList
+    );
+    let list = ExemptionList::try_deserialize(&mut account.try_borrow_data()?.as_ref())?;
+    Ok(list.owners)
+}
+
+// write the exemption list of a state, creating the account on first use
+pub(crate) fn store_exemptions<'info>(
+    account: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    seeds: &[&[u8]],
+    owners: Vec<Pubkey>,
 ) -> Result<()> {
-    let mut map = account.get_map();
-    let record = map.entry(owner).or_insert_with(Vec::new);
+    let list = ExemptionList { owners };
+    let space = list.space();
 
-    require!(record.contains(&target), SolX404Error::InsufficientNFT);
+    if account.data_is_empty() {
+        create_new_account(
+            seeds,
+            Rent::get()?,
+            system_program,
+            payer,
+            account.clone(),
+            space as u64,
+            &crate::ID,
+        )?;
+    } else {
+        resize_account(account.clone(), payer, system_program, space)?;
+    }
 
-    record.retain(|x| x != &target);
+    list.try_serialize(&mut account.try_borrow_mut_data()?.as_mut())?;
+    Ok(())
+}
 
-    account.update_map(&map);
+// the issued NFT mint and its token account of `owner` for every number of a
+// batch, passed in pairs as remaining accounts
+pub(crate) fn batch_nft_accounts<'a, 'info>(
+    remaining_accounts: &'a [AccountInfo<'info>],
+    state: Pubkey,
+    owner: Pubkey,
+    numbers: &[u64],
+) -> Result<Vec<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)>> {
+    require!(
+        !numbers.is_empty() && remaining_accounts.len() == numbers.len() * 2,
+        SolX404Error::InvalidBatchAccounts
+    );
+
+    numbers
+        .iter()
+        .zip(remaining_accounts.chunks(2))
+        .map(|(number, accounts)| {
+            let (mint, _) = Pubkey::find_program_address(
+                &[b"nft_mint", state.as_ref(), number.to_le_bytes().as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(accounts[0].key(), mint, SolX404Error::InvalidBatchAccounts);
+            require_keys_eq!(
+                accounts[1].key(),
+                get_associated_token_address_with_program_id(&owner, &mint, &Token2022::id()),
+                SolX404Error::InvalidBatchAccounts
+            );
+            Ok((&accounts[0], &accounts[1]))
+        })
+        .collect()
+}
+
+// the NFT must be assigned to `signer` in the owner store to be bound
+pub(crate) fn check_bind_holder(holder: Option<Pubkey>, signer: Pubkey, number: u64) -> Result<()> {
+    match holder {
+        None => {
+            msg!("NFT-{} is not assigned", number);
+            err!(SolX404Error::NFTNotAssigned)
+        }
+        Some(holder) if holder != signer => {
+            msg!("NFT-{} is held by {}", number, holder);
+            err!(SolX404Error::NotNFTHolder)
+        }
+        _ => Ok(()),
+    }
+}
+
+// record `mint` as bound to `holder`, bound NFT are still in use so the
+// registry can never outgrow `nft_in_use`
+pub(crate) fn add_bound(
+    registry: &mut BoundRegistry,
+    mint: Pubkey,
+    holder: Pubkey,
+    nft_in_use: u64,
+) -> Result<()> {
+    if let Some(bound) = registry.holder(&mint) {
+        msg!("{} is already bound to {}", mint, bound);
+        return err!(SolX404Error::NFTAlreadyBound);
+    }
 
+    registry.nfts.push(BoundNFT { mint, holder });
+    if registry.nfts.len() as u64 > nft_in_use {
+        msg!(
+            "{} bound NFT but {} in use",
+            registry.nfts.len(),
+            nft_in_use
+        );
+        return err!(SolX404Error::BoundRegistryMismatch);
+    }
+    Ok(())
+}
+
+// a bound NFT being unbound must sit in the signer's account and must not be
+// assigned in the owner store at the same time
+pub(crate) fn check_unbind(
+    owner_store: &OwnerStoreMaps,
+    mint: Pubkey,
+    held: u64,
+    number: u64,
+) -> Result<()> {
+    if held != 1 {
+        msg!("NFT-{} is not in the signer account", number);
+        return err!(SolX404Error::BoundNFTNotHeld);
+    }
+
+    if let Some(holder) = owner_store.index.get(&mint) {
+        msg!("bound NFT-{} is also assigned to {}", number, holder);
+        return err!(SolX404Error::BoundRegistryMismatch);
+    }
+    Ok(())
+}
+
+// remove `mint` from the bound registry, only the recorded holder may unbind
+pub(crate) fn take_bound(
+    registry: &mut BoundRegistry,
+    mint: Pubkey,
+    signer: Pubkey,
+    number: u64,
+) -> Result<()> {
+    match registry.holder(&mint) {
+        None => {
+            msg!("NFT-{} is not bound", number);
+            return err!(SolX404Error::NFTNotBound);
+        }
+        Some(holder) if holder != signer => {
+            msg!("NFT-{} is bound to {}", number, holder);
+            return err!(SolX404Error::NotBoundHolder);
+        }
+        _ => {}
+    }
+
+    registry.nfts.retain(|nft| nft.mint != mint);
+    Ok(())
+}
+
+// hand a bound NFT to `token`, minting it unless an auto bound copy already
+// sits there, the account is frozen only for soulbound states
+pub(crate) fn deliver_bound_nft<'info>(
+    token_program: AccountInfo<'info>,
+    mint: &mut InterfaceAccount<'info, Mint>,
+    token: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    soulbound: bool,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    let (amount, frozen) = {
+        let data = token.try_borrow_data()?;
+        let account = StateWithExtensions::<SPL2022Account>::unpack(&data)?.base;
+        (account.amount, account.is_frozen())
+    };
+    if amount == 0 {
+        mint_nft(
+            token_program.clone(),
+            mint,
+            token.clone(),
+            state.clone(),
+            state_signer,
+        )?;
+    }
+
+    if frozen != soulbound {
+        set_frozen(
+            token_program,
+            mint.to_account_info(),
+            token,
+            state,
+            soulbound,
+            state_signer,
+        )?;
+    }
+    Ok(())
+}
+
+// mirror the owner store assignment of every (NFT mint, token account) pair
+// of `accounts` in the holder wallets: the NFT is minted frozen to an account
+// of its holder and burned from accounts of anyone else, only burned when
+// `mint_copies` is off. Bound NFT, mints issued without permanent delegate
+// and pairs beyond `MAX_AUTO_BIND_PER_CALL` stay as they are.
+pub(crate) fn auto_bind<'info>(
+    accounts: &[AccountInfo<'info>],
+    owner_store: &OwnerStoreMaps,
+    token_program: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    mint_copies: bool,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    require!(
+        accounts.chunks_exact(2).remainder().is_empty(),
+        SolX404Error::InvalidAutoBindAccounts
+    );
+
+    let index = &owner_store.index;
+    for (count, pair) in accounts.chunks(2).enumerate() {
+        if count == MAX_AUTO_BIND_PER_CALL {
+            msg!(
+                "auto bind budget reached, {} left virtual",
+                accounts.len() / 2 - count
+            );
+            break;
+        }
+
+        let (mint, token) = (&pair[0], &pair[1]);
+        let holder = match index.get(mint.key) {
+            Some(holder) => *holder,
+            None => {
+                msg!("{} is not assigned, skip auto bind", mint.key);
+                continue;
+            }
+        };
+
+        require_keys_eq!(
+            *mint.owner,
+            Token2022::id(),
+            SolX404Error::InvalidAutoBindAccounts
+        );
+        require_keys_eq!(
+            *token.owner,
+            Token2022::id(),
+            SolX404Error::InvalidAutoBindAccounts
+        );
+        let (supply, delegate) = {
+            let data = mint.try_borrow_data()?;
+            let mint_state = StateWithExtensions::<SPL2022Mint>::unpack(&data)?;
+            let delegate = mint_state
+                .get_extension::<PermanentDelegate>()
+                .ok()
+                .and_then(|extension| Option::<Pubkey>::from(extension.delegate));
+            (mint_state.base.supply, delegate)
+        };
+        if delegate != Some(state.key()) {
+            msg!("{} has no permanent delegate, skip auto bind", mint.key);
+            continue;
+        }
+
+        let (token_mint, owner, amount, frozen) = {
+            let data = token.try_borrow_data()?;
+            let account = StateWithExtensions::<SPL2022Account>::unpack(&data)?.base;
+            (
+                account.mint,
+                account.owner,
+                account.amount,
+                account.is_frozen(),
+            )
+        };
+        require_keys_eq!(
+            token_mint,
+            mint.key(),
+            SolX404Error::InvalidAutoBindAccounts
+        );
+
+        if amount == 1 && (owner != holder || !mint_copies) {
+            msg!("burn auto bound {} of {}", mint.key, owner);
+            if frozen {
+                set_frozen(
+                    token_program.clone(),
+                    mint.clone(),
+                    token.clone(),
+                    state.clone(),
+                    false,
+                    state_signer,
+                )?;
+            }
+            burn(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    Burn {
+                        mint: mint.clone(),
+                        from: token.clone(),
+                        authority: state.clone(),
+                    },
+                    state_signer,
+                ),
+                1,
+            )?;
+        } else if amount == 0 && owner == holder && supply == 0 && mint_copies {
+            msg!("auto bind {} to {}", mint.key, owner);
+            mint_token(
+                token_program.clone(),
+                mint.clone(),
+                token.clone(),
+                1,
+                state.clone(),
+                state_signer,
+            )?;
+            set_frozen(
+                token_program.clone(),
+                mint.clone(),
+                token.clone(),
+                state.clone(),
+                true,
+                state_signer,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// bound registry of a state, the account is only created by the first bind
+pub(crate) fn load_bound_registry(account: &AccountInfo) -> Result<BoundRegistry> {
+    if account.data_is_empty() {
+        return Ok(BoundRegistry { nfts: Vec::new() });
+    }
+
+    require_keys_eq!(
+        *account.owner,
+        crate::ID,
+        SolX404Error::InvalidBoundRegistry
+    );
+    BoundRegistry::try_deserialize(&mut account.try_borrow_data()?.as_ref())
+}
+
+// write the bound registry of a state, creating the account on first use. A
+// shrink refunds rent to `payer` directly, so write after the CPIs passing it
+pub(crate) fn store_bound_registry<'info>(
+    account: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    seeds: &[&[u8]],
+    registry: BoundRegistry,
+) -> Result<()> {
+    let space = registry.space();
+
+    if account.data_is_empty() {
+        create_new_account(
+            seeds,
+            Rent::get()?,
+            system_program,
+            payer,
+            account.clone(),
+            space as u64,
+            &crate::ID,
+        )?;
+    } else {
+        resize_account(account.clone(), payer, system_program, space)?;
+    }
+
+    registry.try_serialize(&mut account.try_borrow_mut_data()?.as_mut())?;
+    Ok(())
+}
+
+// whether the holder opted out of NFT assignment, the config account is only
+// created when the holder sets the flag for the first time
+pub(crate) fn skip_nft(config: &AccountInfo) -> Result<bool> {
+    if config.data_is_empty() {
+        return Ok(false);
+    }
+
+    require_keys_eq!(*config.owner, crate::ID, SolX404Error::InvalidHolderConfig);
+    let config = HolderConfig::try_deserialize(&mut config.try_borrow_data()?.as_ref())?;
+    Ok(config.skip_nft)
+}
+
+// the owner recorded in `OwnerStore` for NFTs of `owner`, exempted or opted out
+// owners never hold NFTs so their share stays in the pool of `state`
+pub(crate) fn nft_holder(
+    exemptions: &[Pubkey],
+    config: &AccountInfo,
+    owner: Pubkey,
+    state: Pubkey,
+) -> Result<Pubkey> {
+    if exemptions.contains(&owner) || skip_nft(config)? {
+        Ok(state)
+    } else {
+        Ok(owner)
+    }
+}
+
+// number of whole NFT units `balance` is worth
+pub(crate) fn nft_units(balance: u64, fungible_supply: u64) -> Result<usize> {
+    let units = balance
+        .checked_div(fungible_supply)
+        .ok_or(SolX404Error::ArithmeticOverflow)?;
+    Ok(usize::try_from(units).map_err(|_| SolX404Error::ArithmeticOverflow)?)
+}
+
+// number of whole NFT units gained or lost when a balance moves from `before` to `after`
+pub(crate) fn nft_delta(before: u64, after: u64, fungible_supply: u64) -> Result<usize> {
+    Ok(nft_units(before, fungible_supply)?.abs_diff(nft_units(after, fungible_supply)?))
+}
+
+// after a mutation, `owner` must be assigned exactly `balance / fungible_supply`
+// NFTs, or none when its share is held by the pool (`holder` is the state)
+pub(crate) fn check_nft_balance(
+    store: &OwnerStoreMaps,
+    owner: Pubkey,
+    holder: Pubkey,
+    fungible_supply: u64,
+) -> Result<()> {
+    let assigned = store.assigned(&owner);
+    let expected = if holder == owner {
+        nft_units(store.balance(&owner), fungible_supply)?
+    } else {
+        0
+    };
+
+    if assigned != expected {
+        msg!(
+            "{} is assigned {} NFT, expected {}",
+            owner,
+            assigned,
+            expected
+        );
+        return err!(SolX404Error::NFTBalanceMismatch);
+    }
+    Ok(())
+}
+
+// grow or shrink `account` to `space`, topping up the rent from `payer` and
+// refunding the rent a smaller account no longer needs
+pub(crate) fn resize_account<'info>(
+    account: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    top_up_rent(account.clone(), payer.clone(), system_program, space)?;
+
+    account.realloc(space, false)?;
+
+    let required = Rent::get()?.minimum_balance(space);
+    if account.lamports() > required {
+        let excess = account.lamports() - required;
+        **account.try_borrow_mut_lamports()? -= excess;
+        **payer.try_borrow_mut_lamports()? += excess;
+    }
+    Ok(())
+}
+
+// grow an account created before the layout was versioned from `legacy_size`
+// to `size`, the appended fields start zeroed
+pub(crate) fn grow_legacy_account<'info>(
+    account: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    discriminator: [u8; 8],
+    legacy_size: usize,
+    size: usize,
+) -> Result<bool> {
+    require_keys_eq!(*account.owner, crate::ID, SolX404Error::InvalidMigration);
+    if account.try_borrow_data()?[..8] != discriminator {
+        return err!(SolX404Error::InvalidMigration);
+    }
+    if account.data_len() != legacy_size {
+        return Ok(false);
+    }
+
+    resize_account(account.clone(), payer, system_program, size)?;
+    account.try_borrow_mut_data()?[legacy_size..].fill(0);
+    Ok(true)
+}
+
+// rewrite a state created before the layout was versioned, the fields added
+// since take the values `create_x404` gives to new states
+pub(crate) fn migrate_state_account<'info>(
+    state: AccountInfo<'info>,
+    fungible_mint: &AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<bool> {
+    let legacy = {
+        let data = state.try_borrow_data()?;
+        if data[..8] != X404State::DISCRIMINATOR {
+            return err!(SolX404Error::InvalidMigration);
+        }
+        if data.len() != LEGACY_X404_STATE_SIZE {
+            require!(
+                data.len() == X404_STATE_SIZE && data[8] == X404_STATE_VERSION,
+                SolX404Error::InvalidMigration
+            );
+            return Ok(false);
+        }
+        LegacyX404State::deserialize(&mut &data[8..])?
+    };
+
+    // legacy states do not record their hook, the fungible mint does
+    require_keys_eq!(
+        fungible_mint.key(),
+        legacy.fungible_mint,
+        SolX404Error::InvalidMigration
+    );
+    let hook_program = {
+        let mint_data = fungible_mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<SPL2022Mint>::unpack(&mint_data)?;
+        let hook = mint.get_extension::<TransferHook>()?;
+        Option::<Pubkey>::from(hook.program_id).ok_or(SolX404Error::InvalidMigration)?
+    };
+
+    let migrated = X404State {
+        version: X404_STATE_VERSION,
+        source: legacy.source,
+        redeem_max_deadline: legacy.redeem_max_deadline,
+        redeem_fee: legacy.redeem_fee,
+        owner: legacy.owner,
+        decimal: legacy.decimal,
+        fungible_mint: legacy.fungible_mint,
+        fungible_hook: legacy.fungible_hook,
+        collection_mint: legacy.collection_mint,
+        nft_supply: legacy.nft_supply,
+        nft_in_use: legacy.nft_in_use,
+        fungible_supply: legacy.fungible_supply,
+        placeholder_uri: String::new(),
+        reveal_hash: [0; 32],
+        base_uri: String::new(),
+        reroll_fee: 0,
+        randomness: Pubkey::default(),
+        hook_program,
+        royalty_bps: 0,
+        royalty_receiver: legacy.owner,
+        marketplaces: Vec::new(),
+        soulbound: false,
+        auto_bind: false,
+    };
+
+    resize_account(state.clone(), payer, system_program, X404_STATE_SIZE)?;
+    let mut data = state.try_borrow_mut_data()?;
+    data.fill(0);
+    migrated.try_serialize(&mut &mut data[..])?;
+    Ok(true)
+}
+
+// rewrite an owner store created before the layout was versioned, the index is
+// rebuilt from the assignments while the balances are left to `sync_balance`
+pub(crate) fn migrate_owner_store_account<'info>(
+    owner_store: AccountInfo<'info>,
+    source: Pubkey,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<bool> {
+    let legacy = {
+        let data = owner_store.try_borrow_data()?;
+        if data[..8] != OwnerStore::DISCRIMINATOR {
+            return err!(SolX404Error::InvalidMigration);
+        }
+        if data.len() > 40 && data[8..40] == source.to_bytes() {
+            require!(
+                data[40] == OWNER_STORE_VERSION,
+                SolX404Error::InvalidMigration
+            );
+            return Ok(false);
+        }
+        LegacyOwnerStore::deserialize(&mut &data[8..])?
+    };
+
+    let map = HashMap::<Pubkey, Vec<Pubkey>>::try_from_slice(legacy.store.as_slice())?;
+    let mut index = HashMap::<Pubkey, Pubkey>::new();
+    for (owner, mints) in map.iter() {
+        for mint in mints.iter() {
+            index.insert(*mint, *owner);
+        }
+    }
+    let migrated = OwnerStore {
+        source,
+        version: OWNER_STORE_VERSION,
+        store: legacy.store,
+        index: index.try_to_vec()?,
+        balances: HashMap::<Pubkey, AccountBalance>::new().try_to_vec()?,
+    };
+
+    resize_account(owner_store.clone(), payer, system_program, migrated.space())?;
+    let mut data = owner_store.try_borrow_mut_data()?;
+    data.fill(0);
+    migrated.try_serialize(&mut &mut data[..])?;
+    Ok(true)
+}
+
+// make `account` rent exempt for `space` bytes, accounts owned by other
+// programs (e.g. token2022 growing a mint) are funded before they realloc
+pub(crate) fn top_up_rent<'info>(
+    account: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(space);
+    let current = account.lamports();
+
+    if required > current {
+        let transfer_ctx = CpiContext::new(
+            system_program,
+            Transfer {
+                from: payer,
+                to: account,
+            },
+        );
+        transfer(transfer_ctx, required - current)?;
+    }
+    Ok(())
+}
+
+// token group role of an NFT mint
+pub(crate) enum NftGroup<'info> {
+    // the collection mint, a group of at most `max_size` issued NFTs
+    Collection { max_size: u32 },
+    // an issued NFT, member of the collection group
+    Member { group: AccountInfo<'info> },
+}
+
+// create a token2022 NFT mint (0 decimal, state as mint authority) carrying
+// its `metadata` in the mint itself through the metadata pointer extension
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_nft_mint<'info>(
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    mint_seeds: &[&[u8]],
+    state_signer: &[&[&[u8]]],
+    metadata: TokenMetadata,
+    transfer_hook: Option<Pubkey>,
+    permanent_delegate: bool,
+    group: Option<NftGroup<'info>>,
+) -> Result<()> {
+    let mut extensions = vec![ExtensionType::MetadataPointer];
+    if transfer_hook.is_some() {
+        extensions.push(ExtensionType::TransferHook);
+    }
+    if permanent_delegate {
+        // the state burns the auto bound copies as permanent delegate
+        extensions.push(ExtensionType::PermanentDelegate);
+    }
+    match group {
+        Some(NftGroup::Collection { .. }) => extensions.push(ExtensionType::GroupPointer),
+        Some(NftGroup::Member { .. }) => extensions.push(ExtensionType::GroupMemberPointer),
+        None => {}
+    }
+    let mint_size = ExtensionType::try_calculate_account_len::<SPL2022Mint>(&extensions)?;
+
+    create_new_account(
+        mint_seeds,
+        Rent::get()?,
+        system_program.clone(),
+        payer.clone(),
+        mint.clone(),
+        mint_size as u64,
+        token_program.key,
+    )?;
+
+    let pointer_init = metadata_pointer_initialize(
+        token_program.key,
+        mint.key,
+        Some(state.key()),
+        Some(mint.key()),
+    )?;
+    invoke(&pointer_init, std::slice::from_ref(&mint))?;
+
+    match group {
+        Some(NftGroup::Collection { .. }) => {
+            let group_pointer_init = group_pointer_initialize(
+                token_program.key,
+                mint.key,
+                Some(state.key()),
+                Some(mint.key()),
+            )?;
+            invoke(&group_pointer_init, std::slice::from_ref(&mint))?;
+        }
+        Some(NftGroup::Member { .. }) => {
+            let member_pointer_init = group_member_pointer_initialize(
+                token_program.key,
+                mint.key,
+                Some(state.key()),
+                Some(mint.key()),
+            )?;
+            invoke(&member_pointer_init, std::slice::from_ref(&mint))?;
+        }
+        None => {}
+    }
+
+    if let Some(hook_program) = transfer_hook {
+        let hook_init = hook_initialize(
+            token_program.key,
+            mint.key,
+            Some(state.key()),
+            Some(hook_program),
+        )?;
+        invoke(&hook_init, std::slice::from_ref(&mint))?;
+    }
+
+    // only NFT issued while auto bind is on get the state as permanent
+    // delegate, which it only uses in `auto_bind` to burn copies from holder
+    // wallets. The extension cannot be added later, so NFT issued before the
+    // mode was switched on stay virtual.
+    if permanent_delegate {
+        let delegate_init = initialize_permanent_delegate(token_program.key, mint.key, state.key)?;
+        invoke(&delegate_init, std::slice::from_ref(&mint))?;
+    }
+
+    // issued NFT keep the state as freeze authority even while the state is
+    // not soulbound: `set_soulbound` can be switched on after the issue and a
+    // mint without freeze authority can never get one. The state only freezes
+    // bound NFT accounts in `bind` and thaws them in `unbind`, the collection
+    // is never frozen.
+    let freeze_authority = match group {
+        Some(NftGroup::Member { .. }) => Some(state.key),
+        _ => None,
+    };
+    initiate_mint_account(
+        token_program.clone(),
+        mint.clone(),
+        state.clone(),
+        0,
+        freeze_authority,
+    )?;
+
+    // token2022 reallocs the mint for the group and the metadata, fund it
+    // beforehand
+    match group {
+        Some(NftGroup::Collection { .. }) => extensions.push(ExtensionType::TokenGroup),
+        Some(NftGroup::Member { .. }) => extensions.push(ExtensionType::TokenGroupMember),
+        None => {}
+    }
+    let data_size = ExtensionType::try_calculate_account_len::<SPL2022Mint>(&extensions)?
+        + metadata.tlv_size_of()?;
+    top_up_rent(mint.clone(), payer, system_program, data_size)?;
+
+    match group {
+        Some(NftGroup::Collection { max_size }) => {
+            let group_init = initialize_group(
+                token_program.key,
+                mint.key,
+                mint.key,
+                state.key,
+                Some(state.key()),
+                max_size,
+            );
+            invoke_signed(&group_init, &[mint.clone(), state.clone()], state_signer)?;
+        }
+        // the state is both the mint authority of the NFT and the update
+        // authority of the collection group
+        Some(NftGroup::Member { group }) => {
+            let member_init = initialize_member(
+                token_program.key,
+                mint.key,
+                mint.key,
+                state.key,
+                group.key,
+                state.key,
+            );
+            invoke_signed(
+                &member_init,
+                &[mint.clone(), state.clone(), group, state.clone()],
+                state_signer,
+            )?;
+        }
+        None => {}
+    }
+
+    initialize_token_metadata(
+        token_program.clone(),
+        mint.clone(),
+        state.clone(),
+        metadata.name,
+        metadata.symbol,
+        metadata.uri,
+        state_signer,
+    )?;
+
+    for (key, value) in metadata.additional_metadata {
+        update_token_metadata(
+            token_program.clone(),
+            mint.clone(),
+            state.clone(),
+            Field::Key(key),
+            value,
+            state_signer,
+        )?;
+    }
+    Ok(())
+}
+
+// name, symbol and uri of the metaplex metadata of a deposited NFT. Only the
+// fixed header and the strings up to the uri are decoded, the rest of the
+// layout is ignored.
+pub(crate) fn deposit_metadata(metadata: &AccountInfo, mint: Pubkey) -> Result<DepositMetadata> {
+    require_keys_eq!(
+        *metadata.owner,
+        TOKEN_METADATA_PROGRAM_ID,
+        SolX404Error::InvalidDepositMetadata
+    );
+
+    let data = metadata.try_borrow_data()?;
+    // key (MetadataV1 = 4), update authority, mint
+    require!(
+        data.len() > 65 && data[0] == 4 && data[33..65] == mint.to_bytes(),
+        SolX404Error::InvalidDepositMetadata
+    );
+
+    let mut rest = &data[65..];
+    let mut read_string =
+        || String::deserialize(&mut rest).map_err(|_| SolX404Error::InvalidDepositMetadata);
+    let name = read_string()?;
+    let symbol = read_string()?;
+    let uri = read_string()?;
+
+    // metaplex pads the strings with null bytes
+    Ok(DepositMetadata {
+        name: name.trim_end_matches('\0').to_string(),
+        symbol: symbol.trim_end_matches('\0').to_string(),
+        uri: uri.trim_end_matches('\0').to_string(),
+    })
+}
+
+// point the metadata of an issued NFT to a deposited NFT and record the name
+// and symbol of the deposit, keeping the original uri to restore it once the
+// deposit is redeemed
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn link_metadata<'info>(
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    nft_mint: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    deposit: Pubkey,
+    deposit_metadata: DepositMetadata,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    let (metadata, data_len) = {
+        let mint_data = nft_mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<SPL2022Mint>::unpack(&mint_data)?;
+        (
+            mint.get_variable_len_extension::<TokenMetadata>()?,
+            mint_data.len(),
+        )
+    };
+    require!(
+        !metadata
+            .additional_metadata
+            .iter()
+            .any(|(key, _)| key == LINKED_DEPOSIT_KEY),
+        SolX404Error::NFTAlreadyLinked
+    );
+
+    let fields = [
+        (
+            Field::Key(ORIGINAL_URI_KEY.to_string()),
+            metadata.uri.clone(),
+        ),
+        (
+            Field::Key(LINKED_DEPOSIT_KEY.to_string()),
+            deposit.to_string(),
+        ),
+        (
+            Field::Key(DEPOSIT_NAME_KEY.to_string()),
+            deposit_metadata.name,
+        ),
+        (
+            Field::Key(DEPOSIT_SYMBOL_KEY.to_string()),
+            deposit_metadata.symbol,
+        ),
+        (Field::Uri, deposit_metadata.uri),
+    ];
+    let mut linked = metadata.clone();
+    for (field, value) in fields.iter() {
+        linked.update(field.clone(), value.clone());
+    }
+
+    // token2022 reallocs the mint for the metadata, fund it beforehand
+    top_up_rent(
+        nft_mint.clone(),
+        payer,
+        system_program,
+        data_len - metadata.tlv_size_of()? + linked.tlv_size_of()?,
+    )?;
+
+    for (field, value) in fields {
+        update_token_metadata(
+            token_program.clone(),
+            nft_mint.clone(),
+            state.clone(),
+            field,
+            value,
+            state_signer,
+        )?;
+    }
+    Ok(())
+}
+
+// set the final uri of an issued NFT. A linked NFT keeps mirroring its deposit,
+// the uri is stored to be restored on redeem.
+pub(crate) fn update_nft_uri<'info>(
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    nft_mint: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    uri: String,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    let (metadata, data_len) = {
+        let mint_data = nft_mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<SPL2022Mint>::unpack(&mint_data)?;
+        (
+            mint.get_variable_len_extension::<TokenMetadata>()?,
+            mint_data.len(),
+        )
+    };
+    let field = if metadata
+        .additional_metadata
+        .iter()
+        .any(|(key, _)| key == ORIGINAL_URI_KEY)
+    {
+        Field::Key(ORIGINAL_URI_KEY.to_string())
+    } else {
+        Field::Uri
+    };
+
+    let mut updated = metadata.clone();
+    updated.update(field.clone(), uri.clone());
+    top_up_rent(
+        nft_mint.clone(),
+        payer,
+        system_program,
+        data_len - metadata.tlv_size_of()? + updated.tlv_size_of()?,
+    )?;
+
+    update_token_metadata(token_program, nft_mint, state, field, uri, state_signer)
+}
+
+// publish the creator royalty in the collection metadata for marketplaces
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn publish_royalty<'info>(
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    collection_mint: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    royalty_bps: u16,
+    royalty_receiver: Pubkey,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    let (metadata, data_len) = {
+        let mint_data = collection_mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<SPL2022Mint>::unpack(&mint_data)?;
+        (
+            mint.get_variable_len_extension::<TokenMetadata>()?,
+            mint_data.len(),
+        )
+    };
+
+    let fields = [
+        (
+            Field::Key(ROYALTY_BPS_KEY.to_string()),
+            royalty_bps.to_string(),
+        ),
+        (
+            Field::Key(ROYALTY_RECEIVER_KEY.to_string()),
+            royalty_receiver.to_string(),
+        ),
+    ];
+    let mut published = metadata.clone();
+    for (field, value) in fields.iter() {
+        published.update(field.clone(), value.clone());
+    }
+
+    top_up_rent(
+        collection_mint.clone(),
+        payer,
+        system_program,
+        data_len - metadata.tlv_size_of()? + published.tlv_size_of()?,
+    )?;
+
+    for (field, value) in fields {
+        update_token_metadata(
+            token_program.clone(),
+            collection_mint.clone(),
+            state.clone(),
+            field,
+            value,
+            state_signer,
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn verify_reveal_proof(root: [u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| reveal_parent(node, *sibling))
+        == root
+}
+
+// restore the original metadata of an issued NFT linked by `link_metadata`
+pub(crate) fn unlink_metadata<'info>(
+    token_program: AccountInfo<'info>,
+    nft_mint: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    let metadata = {
+        let mint_data = nft_mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<SPL2022Mint>::unpack(&mint_data)?;
+        mint.get_variable_len_extension::<TokenMetadata>()?
+    };
+    let original_uri = metadata
+        .additional_metadata
+        .iter()
+        .find(|(key, _)| key == ORIGINAL_URI_KEY)
+        .map(|(_, value)| value.clone())
+        .ok_or(SolX404Error::InvalidLinkedNFT)?;
+
+    update_token_metadata(
+        token_program.clone(),
+        nft_mint.clone(),
+        state.clone(),
+        Field::Uri,
+        original_uri,
+        state_signer,
+    )?;
+
+    for key in [
+        LINKED_DEPOSIT_KEY,
+        DEPOSIT_NAME_KEY,
+        DEPOSIT_SYMBOL_KEY,
+        ORIGINAL_URI_KEY,
+    ] {
+        let instruction = remove_key(
+            token_program.key,
+            nft_mint.key,
+            state.key,
+            key.to_string(),
+            false,
+        );
+        invoke_signed(
+            &instruction,
+            &[nft_mint.clone(), state.clone(), token_program.clone()],
+            state_signer,
+        )?;
+    }
+    Ok(())
+}
+
+// write the token metadata stored in the mint itself, the state is both the
+// mint authority and the update authority
+pub(crate) fn initialize_token_metadata<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    name: String,
+    symbol: String,
+    uri: String,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    let instruction = metadata_initialize(
+        token_program.key,
+        mint.key,
+        state.key,
+        mint.key,
+        state.key,
+        name,
+        symbol,
+        uri,
+    );
+
+    invoke_signed(&instruction, &[mint, state, token_program], state_signer)?;
+    Ok(())
+}
+
+pub(crate) fn update_token_metadata<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    field: Field,
+    value: String,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    let instruction = update_field(token_program.key, mint.key, state.key, field, value);
+
+    invoke_signed(&instruction, &[mint, state, token_program], state_signer)?;
     Ok(())
 }
 
@@ -225,11 +1696,99 @@ pub(crate) fn create_new_account<'info>(
     Ok(())
 }
 
+// anchor discriminator of `initialize_extra_account_meta_list` in the hook program
+const INIT_META_LIST_DISCRIMINATOR: [u8; 8] = [92, 197, 174, 197, 41, 124, 19, 3];
+
+// anchor discriminator of `initialize_nft_meta_list` in the hook program
+const INIT_NFT_META_LIST_DISCRIMINATOR: [u8; 8] = [204, 195, 215, 160, 31, 121, 54, 243];
+
+// let the hook program create the extra account meta list of an issued NFT
+// mint, every NFT transfer is checked by the hook
+pub(crate) fn initialize_nft_meta_list<'info>(
+    hook_program: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    extra_account: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    state: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    state_signer: &[&[&[u8]]],
+) -> Result<()> {
+    let instruction = Instruction {
+        program_id: hook_program.key(),
+        accounts: vec![
+            AccountMeta::new(payer.key(), true),
+            AccountMeta::new(extra_account.key(), false),
+            AccountMeta::new_readonly(mint.key(), false),
+            AccountMeta::new_readonly(state.key(), true),
+            AccountMeta::new_readonly(system_program.key(), false),
+        ],
+        data: INIT_NFT_META_LIST_DISCRIMINATOR.to_vec(),
+    };
+
+    invoke_signed(
+        &instruction,
+        &[
+            payer,
+            extra_account,
+            mint,
+            state,
+            system_program,
+            hook_program,
+        ],
+        state_signer,
+    )?;
+    Ok(())
+}
+
+// let the hook program create the extra account meta list of `mint`, so the
+// fungible mint never exists without its meta list
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn initialize_hook_meta_list<'info>(
+    hook_program: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    extra_account: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    associated_token_program: AccountInfo<'info>,
+    x404_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<()> {
+    let instruction = Instruction {
+        program_id: hook_program.key(),
+        accounts: vec![
+            AccountMeta::new(payer.key(), true),
+            AccountMeta::new(extra_account.key(), false),
+            AccountMeta::new_readonly(mint.key(), false),
+            AccountMeta::new_readonly(associated_token_program.key(), false),
+            AccountMeta::new_readonly(x404_program.key(), false),
+            AccountMeta::new_readonly(token_program.key(), false),
+            AccountMeta::new_readonly(system_program.key(), false),
+        ],
+        data: INIT_META_LIST_DISCRIMINATOR.to_vec(),
+    };
+
+    invoke(
+        &instruction,
+        &[
+            payer,
+            extra_account,
+            mint,
+            associated_token_program,
+            x404_program,
+            token_program,
+            system_program,
+            hook_program,
+        ],
+    )?;
+    Ok(())
+}
+
 pub(crate) fn initiate_mint_account<'info>(
     token_program: AccountInfo<'info>,
     mint_account: AccountInfo<'info>,
     authority: AccountInfo<'info>,
     decimal: u8,
+    freeze_authority: Option<&Pubkey>,
 ) -> Result<()> {
     let init_mint_ctx = CpiContext::new(
         token_program,
@@ -238,12 +1797,12 @@ pub(crate) fn initiate_mint_account<'info>(
         },
     );
 
-    initialize_mint2(init_mint_ctx, decimal, &authority.key, None)?;
+    initialize_mint2(init_mint_ctx, decimal, &authority.key, freeze_authority)?;
     Ok(())
 }
 
-pub(crate) fn do_rebalance<'info>(
-    owner_store: &mut Account<'info, OwnerStore>,
+pub(crate) fn do_rebalance(
+    owner_store: &mut OwnerStoreMaps,
     sender: Pubkey,
     receiver: Pubkey,
     state: Pubkey,
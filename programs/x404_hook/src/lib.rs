@@ -1,13 +1,23 @@
 use anchor_lang::{
     prelude::*,
-    system_program::{self, create_account, CreateAccount}, InstructionData,
+    system_program::{self, create_account, transfer, CreateAccount, Transfer}, InstructionData,
 };
 use anchor_spl::{
-    associated_token::AssociatedToken, token_2022::Token2022, token_interface::{Mint, TokenAccount}
+    associated_token::AssociatedToken,
+    token_2022::{
+        spl_token_2022::{
+            extension::{
+                transfer_hook::TransferHookAccount, BaseStateWithExtensions, StateWithExtensions,
+            },
+            state::{Account as Token2022Account, Mint as Token2022Mint},
+        },
+        Token2022,
+    },
+    token_interface::{Mint, TokenAccount}
 };
-use solana_program::instruction::Instruction;
+use solana_program::{instruction::Instruction, program_option::COption};
 use spl_tlv_account_resolution::{
-    account::ExtraAccountMeta, state::ExtraAccountMetaList,
+    account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
 };
 use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
 use x404::{program::X404, state::{OwnerStore, X404State}};
@@ -17,6 +27,23 @@ use x404::{program::X404, state::{OwnerStore, X404State}};
 
 declare_id!("6uCDZftnA5YaTmqv3PnGSQSomFpnrtvkrk2xQSHrvNgh");
 
+// offset of the mint authority (the x404 state) in the fungible mint data
+const MINT_AUTHORITY_OFFSET: u8 = 4;
+// offset of `source` in the owner store data, after the discriminator
+const OWNER_STORE_SOURCE_OFFSET: u8 = 8;
+
+#[error_code]
+pub enum TransferError {
+    #[msg("The token is not currently transferring")]
+    IsNotCurrentlyTransferring,
+    #[msg("Only the owner of the x404 state can update the meta list")]
+    NotStateOwner,
+    #[msg("The transfer authority is not an allowed marketplace")]
+    MarketplaceNotAllowed,
+    #[msg("Only the x404 state minting the NFT can create its meta list")]
+    NotMintAuthority,
+}
+
 #[program]
 #[cfg(not(feature = "no-entrypoint"))]
 pub mod transfer_hook {
@@ -24,83 +51,107 @@ pub mod transfer_hook {
     use std::ops::Deref;
 
     use solana_program::program::invoke_signed;
-    use x404::instructions::rebalance;
+    use x404::instructions::{rebalance, transfer_bound};
 
     use super::*;
 
     pub fn initialize_extra_account_meta_list(
         ctx: Context<InitializeExtraAccountMetaList>,
     ) -> Result<()> {
-        // index 0-3 are the accounts required for token transfer (source, mint, destination, owner=mint)
-        // index 4 is address of ExtraAccountMetaList account
-        let account_metas = vec![
-            // index 5, 404 state
-            ExtraAccountMeta::new_with_pubkey(&ctx.accounts.state.key(), false, false)?,
-            // index 6, 404 owner store
-            ExtraAccountMeta::new_with_pubkey(&ctx.accounts.owner_store.key(), false, true)?,
-            // index 7, associated token program
-            ExtraAccountMeta::new_with_pubkey(
-                &ctx.accounts.associated_token_program.key(),
-                false,
-                false,
-            )?,
-            // index 8, associated token program
-            ExtraAccountMeta::new_with_pubkey(
-                &ctx.accounts.x404_program.key(),
-                false,
-                false,
-            )?,
-            // index 9, token program
-            ExtraAccountMeta::new_with_pubkey(
-                &ctx.accounts.token_program.key(),
-                false,
-                false,)?,
-        ];
-
-        // calculate account size
-        let account_size = ExtraAccountMetaList::size_of(account_metas.len())? as u64;
-        // calculate minimum required lamports
-        let lamports = Rent::get()?.minimum_balance(account_size as usize);
-
-        let mint = ctx.accounts.mint.key();
-        let signer_seeds: &[&[&[u8]]] = &[&[
-            b"extra-account-metas",
-            &mint.as_ref(),
-            &[ctx.bumps.extra_account_meta_list],
-        ]];
-
-        // create ExtraAccountMetaList account
-        create_account(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                CreateAccount {
-                    from: ctx.accounts.payer.to_account_info(),
-                    to: ctx.accounts.extra_account_meta_list.to_account_info(),
-                },
-            )
-            .with_signer(signer_seeds),
-            lamports,
-            account_size,
+        create_meta_list(
             ctx.program_id,
-        )?;
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.extra_account_meta_list.to_account_info(),
+            ctx.accounts.mint.key(),
+            ctx.bumps.extra_account_meta_list,
+            ctx.accounts.system_program.to_account_info(),
+            &extra_account_metas()?,
+        )
+    }
+
+    // meta list of an issued NFT mint, created by x404 along with the mint
+    pub fn initialize_nft_meta_list(ctx: Context<InitializeNftMetaList>) -> Result<()> {
+        create_meta_list(
+            ctx.program_id,
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.extra_account_meta_list.to_account_info(),
+            ctx.accounts.mint.key(),
+            ctx.bumps.extra_account_meta_list,
+            ctx.accounts.system_program.to_account_info(),
+            &nft_extra_account_metas()?,
+        )
+    }
 
-        // initialize ExtraAccountMetaList account with extra accounts
-        ExtraAccountMetaList::init::<ExecuteInstruction>(
-            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+    // rewrite the meta list of the fungible mint or of an issued NFT mint, so
+    // lists created by an older version get the accounts added since
+    pub fn update_extra_account_meta_list(
+        ctx: Context<UpdateExtraAccountMetaList>,
+    ) -> Result<()> {
+        let account_metas = if ctx.accounts.mint.key() == ctx.accounts.state.fungible_mint {
+            extra_account_metas()?
+        } else {
+            nft_extra_account_metas()?
+        };
+        let account_size = ExtraAccountMetaList::size_of(account_metas.len())?;
+        let meta_list = ctx.accounts.extra_account_meta_list.to_account_info();
+
+        // grow before rewriting so the new list fits, shrink afterwards
+        if account_size > meta_list.data_len() {
+            let lamports = Rent::get()?.minimum_balance(account_size);
+            if lamports > meta_list.lamports() {
+                transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.owner.to_account_info(),
+                            to: meta_list.clone(),
+                        },
+                    ),
+                    lamports - meta_list.lamports(),
+                )?;
+            }
+            meta_list.realloc(account_size, false)?;
+        }
+
+        ExtraAccountMetaList::update::<ExecuteInstruction>(
+            &mut meta_list.try_borrow_mut_data()?,
             &account_metas,
         )?;
 
+        if account_size < meta_list.data_len() {
+            meta_list.realloc(account_size, false)?;
+
+            // the shrunk list needs less rent, the owner who paid it gets the rest back
+            let lamports = Rent::get()?.minimum_balance(account_size);
+            if meta_list.lamports() > lamports {
+                let excess = meta_list.lamports() - lamports;
+                **meta_list.try_borrow_mut_lamports()? -= excess;
+                **ctx.accounts.owner.to_account_info().try_borrow_mut_lamports()? += excess;
+            }
+        }
+
         Ok(())
     }
 
     pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
         let mint_key = ctx.accounts.mint.key();
         let signer_seeds: &[&[&[u8]]] = &[&[b"extra-account-metas",mint_key.as_ref(), &[ctx.bumps.extra_account_meta_list]]];
-        msg!("Rebalance the state"); 
+
+        // the rebalance trusts `amount`, so only run inside a token2022 transfer
+        check_is_transferring(&ctx.accounts.source_token.to_account_info())?;
+
+        // the NFT movement is attributed to the token account owners, not to
+        // the signing authority which may be a delegate.
+        // accounts of the same owner share one NFT assignment, the state still
+        // records the amount of both accounts
+        msg!("Rebalance the state");
        
         let instruction = rebalance(
             ctx.accounts.state.key(),
             ctx.accounts.owner_store.key(),
+            ctx.accounts.exemption.key(),
+            ctx.accounts.source_config.key(),
+            ctx.accounts.destination_config.key(),
             ctx.accounts.source_token.deref().owner,
             ctx.accounts.destination_token.deref().owner,
             amount,
@@ -113,6 +164,9 @@ pub mod transfer_hook {
             &[
             ctx.accounts.state.to_account_info(),
             ctx.accounts.owner_store.to_account_info(),
+            ctx.accounts.exemption.to_account_info(),
+            ctx.accounts.source_config.to_account_info(),
+            ctx.accounts.destination_config.to_account_info(),
             ctx.accounts.mint.to_account_info(),
             ctx.accounts.source_token.to_account_info(),
             ctx.accounts.destination_token.to_account_info(),
@@ -124,6 +178,47 @@ pub mod transfer_hook {
         Ok(())
     }
 
+    // transfer of an issued NFT, only allowed marketplaces may move it when
+    // the state has an allowlist
+    pub fn nft_transfer_hook(ctx: Context<NftTransferHook>, _amount: u64) -> Result<()> {
+        check_is_transferring(&ctx.accounts.source_token.to_account_info())?;
+
+        // a holder moving the NFT between his own accounts is not a sale
+        if ctx.accounts.source_token.owner == ctx.accounts.destination_token.owner {
+            return Ok(());
+        }
+
+        let marketplaces = &ctx.accounts.state.marketplaces;
+        require!(
+            marketplaces.is_empty() || marketplaces.contains(&ctx.accounts.owner.key()),
+            TransferError::MarketplaceNotAllowed
+        );
+
+        // the receiver becomes the one entitled to unbind the NFT
+
+        let mint_key = ctx.accounts.mint.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"extra-account-metas", mint_key.as_ref(), &[ctx.bumps.extra_account_meta_list]]];
+        let instruction = transfer_bound(
+            ctx.accounts.state.key(),
+            ctx.accounts.bound_registry.key(),
+            mint_key,
+            ctx.accounts.source_token.key(),
+            ctx.accounts.destination_token.key(),
+            ctx.accounts.extra_account_meta_list.key());
+
+        invoke_signed(&instruction,
+            &[
+            ctx.accounts.state.to_account_info(),
+            ctx.accounts.bound_registry.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.source_token.to_account_info(),
+            ctx.accounts.destination_token.to_account_info(),
+            ctx.accounts.extra_account_meta_list.to_account_info(), ],
+            signer_seeds)?;
+
+        Ok(())
+    }
+
     // fallback instruction handler as workaround to anchor instruction discriminator check
     pub fn fallback<'info>(
         program_id: &Pubkey,
@@ -138,6 +233,17 @@ pub mod transfer_hook {
             TransferHookInstruction::Execute { amount } => {
                 let amount_bytes = amount.to_le_bytes();
 
+                // the fungible mint and the issued NFT mints share the x404
+                // state as mint authority, they have their own hook accounts
+                let mint = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                if !is_fungible_mint(mint)? {
+                    return __private::__global::nft_transfer_hook(
+                        program_id,
+                        accounts,
+                        &amount_bytes,
+                    );
+                }
+
                 // invoke custom transfer hook instruction on our program
                 __private::__global::transfer_hook(program_id, accounts, &amount_bytes)
             }
@@ -146,6 +252,199 @@ pub mod transfer_hook {
     }
 }
 
+// only the fungible mint is the `fungible_mint` PDA of its mint authority
+fn is_fungible_mint(mint: &AccountInfo) -> Result<bool> {
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Token2022Mint>::unpack(&data)?;
+    let COption::Some(state) = mint_state.base.mint_authority else {
+        return Ok(false);
+    };
+    let (fungible_mint, _) = Pubkey::find_program_address(
+        &[b"fungible_mint", state.as_ref()],
+        &x404::ID,
+    );
+    Ok(fungible_mint == mint.key())
+}
+
+// accounts required by `transfer_hook` on top of the transfer accounts
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    // index 0-3 are the accounts required for token transfer (source, mint, destination, owner=mint)
+    // index 4 is address of ExtraAccountMetaList account
+    // only the programs are fixed, every x404 account is derived from
+    // seeds so the same list layout serves all collections.
+    Ok(vec![
+        // index 5, associated token program
+        ExtraAccountMeta::new_with_pubkey(
+            &AssociatedToken::id(),
+            false,
+            false,
+        )?,
+        // index 6, x404 program
+        ExtraAccountMeta::new_with_pubkey(
+            &x404::ID,
+            false,
+            false,
+        )?,
+        // index 7, token program
+        ExtraAccountMeta::new_with_pubkey(
+            &Token2022::id(),
+            false,
+            false,)?,
+        // index 8, 404 owner store, x404 PDA of the state which is the
+        // mint authority of the fungible mint
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            6,
+            &[
+                Seed::Literal { bytes: b"owner_store".to_vec() },
+                Seed::AccountData { account_index: 1, data_index: MINT_AUTHORITY_OFFSET, length: 32 },
+            ],
+            false,
+            true,
+        )?,
+        // index 9, 404 state, x404 PDA of the source recorded in the owner store
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            6,
+            &[
+                Seed::Literal { bytes: b"state".to_vec() },
+                Seed::AccountData { account_index: 8, data_index: OWNER_STORE_SOURCE_OFFSET, length: 32 },
+            ],
+            false,
+            false,
+        )?,
+        // index 10, 404 exemption list
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            6,
+            &[
+                Seed::Literal { bytes: b"exemption".to_vec() },
+                Seed::AccountKey { index: 9 },
+            ],
+            false,
+            false,
+        )?,
+        // index 11, holder config of the source owner, x404 PDA
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            6,
+            &[
+                Seed::Literal { bytes: b"holder".to_vec() },
+                Seed::AccountKey { index: 9 },
+                // owner of the source token account
+                Seed::AccountData { account_index: 0, data_index: 32, length: 32 },
+            ],
+            false,
+            false,
+        )?,
+        // index 12, holder config of the destination owner, x404 PDA
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            6,
+            &[
+                Seed::Literal { bytes: b"holder".to_vec() },
+                Seed::AccountKey { index: 9 },
+                // owner of the destination token account
+                Seed::AccountData { account_index: 2, data_index: 32, length: 32 },
+            ],
+            false,
+            false,
+        )?,
+    ])
+}
+
+// accounts required by `nft_transfer_hook` on top of the transfer accounts
+fn nft_extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    Ok(vec![
+        // index 5, x404 program
+        ExtraAccountMeta::new_with_pubkey(
+            &x404::ID,
+            false,
+            false,
+        )?,
+        // index 6, 404 owner store, x404 PDA of the state which is the
+        // mint authority of the NFT mint
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            5,
+            &[
+                Seed::Literal { bytes: b"owner_store".to_vec() },
+                Seed::AccountData { account_index: 1, data_index: MINT_AUTHORITY_OFFSET, length: 32 },
+            ],
+            false,
+            false,
+        )?,
+        // index 7, 404 state, x404 PDA of the source recorded in the owner store
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            5,
+            &[
+                Seed::Literal { bytes: b"state".to_vec() },
+                Seed::AccountData { account_index: 6, data_index: OWNER_STORE_SOURCE_OFFSET, length: 32 },
+            ],
+            false,
+            false,
+        )?,
+        // index 8, 404 bound registry, moves the unbind right with the NFT
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            5,
+            &[
+                Seed::Literal { bytes: b"bound".to_vec() },
+                Seed::AccountKey { index: 7 },
+            ],
+            false,
+            true,
+        )?,
+    ])
+}
+
+// create the meta list PDA of `mint` holding `account_metas`
+fn create_meta_list<'info>(
+    program_id: &Pubkey,
+    payer: AccountInfo<'info>,
+    meta_list: AccountInfo<'info>,
+    mint: Pubkey,
+    bump: u8,
+    system_program: AccountInfo<'info>,
+    account_metas: &[ExtraAccountMeta],
+) -> Result<()> {
+    // calculate account size
+    let account_size = ExtraAccountMetaList::size_of(account_metas.len())? as u64;
+    // calculate minimum required lamports
+    let lamports = Rent::get()?.minimum_balance(account_size as usize);
+
+    let signer_seeds: &[&[&[u8]]] = &[&[b"extra-account-metas", mint.as_ref(), &[bump]]];
+
+    // create ExtraAccountMetaList account
+    create_account(
+        CpiContext::new(
+            system_program,
+            CreateAccount {
+                from: payer,
+                to: meta_list.clone(),
+            },
+        )
+        .with_signer(signer_seeds),
+        lamports,
+        account_size,
+        program_id,
+    )?;
+
+    // initialize ExtraAccountMetaList account with extra accounts
+    ExtraAccountMetaList::init::<ExecuteInstruction>(
+        &mut meta_list.try_borrow_mut_data()?,
+        account_metas,
+    )?;
+
+    Ok(())
+}
+
+fn check_is_transferring(source_token_info: &AccountInfo) -> Result<()> {
+    let account_data = source_token_info.try_borrow_data()?;
+    let account = StateWithExtensions::<Token2022Account>::unpack(&account_data)?;
+    let extension = account.get_extension::<TransferHookAccount>()?;
+
+    require!(
+        bool::from(extension.transferring),
+        TransferError::IsNotCurrentlyTransferring
+    );
+
+    Ok(())
+}
+
 #[derive(Accounts)]
 pub struct InitializeExtraAccountMetaList<'info> {
     #[account(mut)]
@@ -158,23 +457,65 @@ pub struct InitializeExtraAccountMetaList<'info> {
     )]
     pub extra_account_meta_list: AccountInfo<'info>,
     pub mint: InterfaceAccount<'info, Mint>,
-    pub state: Account<'info, X404State>,
-    pub owner_store: Account<'info, OwnerStore>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub x404_program: Program<'info, X404>,
     pub token_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeNftMetaList<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: ExtraAccountMetaList Account, must use these seeds
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: AccountInfo<'info>,
+    #[account(
+        constraint = mint.mint_authority == COption::Some(state.key()) @ TransferError::NotMintAuthority,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    // the x404 state signs through `issue_token`
+    #[account(owner = x404::ID @ TransferError::NotMintAuthority)]
+    pub state: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: ExtraAccountMetaList Account, must use these seeds
+    #[account(
+        mut,
+        seeds = [b"extra-account-metas", mint.key().as_ref()], 
+        bump
+    )]
+    pub extra_account_meta_list: AccountInfo<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    // the fungible mint or an issued NFT mint, the collection has no hook
+    #[account(
+        constraint = state.fungible_mint == mint.key()
+            || (mint.mint_authority == COption::Some(state.key()) && state.collection_mint != mint.key()),
+        constraint = state.owner == owner.key() @ TransferError::NotStateOwner,
+    )]
+    pub state: Account<'info, X404State>,
+    pub system_program: Program<'info, System>,
+}
+
 // Order of accounts matters for this struct.
 // The first 4 accounts are the accounts required for token transfer (source, mint, destination, owner)
 // Remaining accounts are the extra accounts required from the ExtraAccountMetaList account
 // These accounts are provided via CPI to this program from the token2022 program
 #[derive(Accounts)]
 pub struct TransferHook<'info> {
+    // the transfer may be signed by a delegate or the permanent delegate
+    // rather than the owner, token2022 already checked the authority.
     #[account(
         token::mint = mint, 
-        token::authority = owner,
     )]
     pub source_token: InterfaceAccount<'info, TokenAccount>,
     pub mint: InterfaceAccount<'info, Mint>,
@@ -182,27 +523,62 @@ pub struct TransferHook<'info> {
         token::mint = mint,
     )]
     pub destination_token: InterfaceAccount<'info, TokenAccount>,
-    pub owner: AccountInfo<'info>,
+    /// CHECK: transfer authority, NFTs always follow the token account owners
+    pub owner: UncheckedAccount<'info>,
     /// CHECK: ExtraAccountMetaList Account,
     #[account(
         seeds = [b"extra-account-metas", mint.key().as_ref()], 
         bump
     )]
     pub extra_account_meta_list: UncheckedAccount<'info>,
-    pub state: Account<'info, X404State>,
-    #[account(mut)] 
-    pub owner_store: Account<'info, OwnerStore>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub x404_program: Program<'info, X404>,
     pub token_program: Program<'info, Token2022>,
+    #[account(mut)] 
+    pub owner_store: Account<'info, OwnerStore>,
+    #[account(constraint = state.fungible_mint == mint.key())]
+    pub state: Account<'info, X404State>,
+    /// CHECK: exemption list of the state, checked by x404
+    pub exemption: UncheckedAccount<'info>,
+    /// CHECK: holder config of the source owner, checked by x404
+    pub source_config: UncheckedAccount<'info>,
+    /// CHECK: holder config of the destination owner, checked by x404
+    pub destination_config: UncheckedAccount<'info>,
+}
+
+// Order of accounts matters, see `nft_extra_account_metas`
+#[derive(Accounts)]
+pub struct NftTransferHook<'info> {
+    #[account(
+        token::mint = mint,
+    )]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        token::mint = mint,
+    )]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: transfer authority, the owner or a delegate such as a marketplace
+    pub owner: UncheckedAccount<'info>,
+    /// CHECK: ExtraAccountMetaList Account,
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    pub x404_program: Program<'info, X404>,
+    pub owner_store: Account<'info, OwnerStore>,
+    #[account(constraint = mint.mint_authority == COption::Some(state.key()))]
+    pub state: Account<'info, X404State>,
+    /// CHECK: bound registry of the state, checked by x404
+    #[account(mut)]
+    pub bound_registry: UncheckedAccount<'info>,
 }
 
 pub fn initialize_extra_account(
     extra_account: Pubkey,
     fungible_mint: Pubkey,
     owner: Pubkey,
-    x404_state: Pubkey,
-    owner_store: Pubkey,
 )-> Instruction{
     let data = instruction::InitializeExtraAccountMetaList {
     };
@@ -213,12 +589,31 @@ pub fn initialize_extra_account(
             AccountMeta::new_readonly(owner, true),
             AccountMeta::new(extra_account, false),
             AccountMeta::new_readonly(fungible_mint, false),
-            AccountMeta::new_readonly(x404_state, false),
-            AccountMeta::new_readonly(owner_store, false),
             AccountMeta::new_readonly(AssociatedToken::id(), false),
             AccountMeta::new_readonly(x404::id(), false),
             AccountMeta::new_readonly(Token2022::id(), false),
             AccountMeta::new_readonly(system_program::ID, false),
         ],
     )
-}
\ No newline at end of file
+}
+
+pub fn update_extra_account(
+    extra_account: Pubkey,
+    mint: Pubkey,
+    x404_state: Pubkey,
+    owner: Pubkey,
+)-> Instruction{
+    let data = instruction::UpdateExtraAccountMetaList {
+    };
+    Instruction::new_with_bytes(
+        ID,
+        &data.data(),
+        vec![
+            AccountMeta::new(owner, true),
+            AccountMeta::new(extra_account, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(x404_state, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+    )
+}
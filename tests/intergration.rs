@@ -1,17 +1,22 @@
-use std::vec;
+use std::{collections::HashMap, vec};
 
-use anchor_lang::{AccountDeserialize, Id, Key};
+use anchor_lang::{
+    AccountDeserialize, AnchorDeserialize, AnchorSerialize, Discriminator, Id, InstructionData,
+    Key, ToAccountMetas,
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::spl_token,
     token_2022::{
         self,
-        spl_token_2022::{self, instruction::TokenInstruction},
+        spl_token_2022::{self, extension::ExtensionType, instruction::TokenInstruction},
         Token2022,
     },
     token_interface::TokenAccount,
 };
-use solana_program::instruction::Instruction;
+use solana_program::{
+    clock::Clock, instruction::Instruction, program_option::COption, slot_hashes::SlotHashes,
+};
 use solana_program_test::{
     tokio::{self},
     BanksClientError, ProgramTest, ProgramTestContext,
@@ -24,23 +29,46 @@ use solana_sdk::{
     signature::Keypair,
     signer::Signer,
     system_instruction::{self, create_account},
+    sysvar,
     transaction::Transaction,
 };
 use spl_associated_token_account::{
     get_associated_token_address_with_program_id, instruction::create_associated_token_account,
 };
+// token2022 1.0 also parses the token group extensions
+use ::spl_token_2022::{
+    extension::{
+        permanent_delegate::PermanentDelegate, BaseStateWithExtensions, Extension,
+        StateWithExtensions,
+    },
+    state::Mint,
+};
+use spl_token_group_interface::state::{TokenGroup, TokenGroupMember};
+use spl_token_metadata_interface::state::TokenMetadata;
 use spl_transfer_hook_interface::get_extra_account_metas_address;
 
 use x404::{
-    state::{X404Hub, X404State},
+    state::{
+        reroll_pick, reveal_leaf, reveal_parent, AuditDiscrepancy, AuditView, BankInfoView,
+        BoundRegistry, LegacyOwnerStore, LegacyX404State, NFTBank, NFTHolderView, OwnerNFTsView,
+        RerollRequest, StateSummaryView, X404Hub, X404State, OWNER_STORE_VERSION,
+        X404_STATE_VERSION,
+    },
     ID,
 };
-use x404_hook::{initialize_extra_account, ID as HookID};
+use x404_hook::{update_extra_account, ID as HookID};
 
 const REDEEMFEE: u64 = 100;
 const REDEEM_MAX_DEADLINE: u64 = 100;
 const DECIMALS: u8 = 2;
 const FUNGIBLE_SUPPLY: u64 = 1000;
+const FUNGIBLE_NAME: &str = "test_token";
+const FUNGIBLE_SYMBOL: &str = "TT";
+const FUNGIBLE_URI: &str = "https://example.com/test_token.json";
+const REROLL_FEE: u64 = 100;
+const PLACEHOLDER_URI: &str = "https://example.com/unrevealed.json";
+const REVEAL_BASE_URI: &str = "https://example.com/revealed";
+const COLLECTION_MAX_SIZE: u32 = 100;
 
 #[cfg(test)]
 #[tokio::test]
@@ -50,6 +78,12 @@ async fn functionality_test() {
     let mut validator = ProgramTest::default();
     validator.add_program("X404", ID, None);
     validator.add_program("X404_HOOK", HookID, None);
+    // the bundled token2022 lacks the token group instructions
+    validator.add_program(
+        "spl_token_2022",
+        spl_token_2022::ID,
+        solana_program_test::processor!(::spl_token_2022::processor::Processor::process),
+    );
     // validator.add_program("spl_token_2022", spl_token_2022::ID, None);
     // validator.add_program("metaplex_token_metadata_program", metadata::ID, None);
 
@@ -57,6 +91,7 @@ async fn functionality_test() {
 
     let usera = add_account(&mut validator, 100);
     let userb = add_account(&mut validator, 100);
+    let marketplace = add_account(&mut validator, 10);
 
     let hub_state = add_pda(&[b"hub".as_ref()], ID);
     let source = add_pda(&[b"test_mint".as_ref()], Token2022::id());
@@ -96,6 +131,23 @@ async fn functionality_test() {
     )
     .await;
 
+    // switched on before any NFT is issued, only those mints get the state as
+    // permanent delegate
+    execute(
+        &mut context,
+        &owner,
+        &[x404::instructions::set_auto_bind(
+            true,
+            source,
+            hub_state,
+            x404_state,
+            owner.pubkey(),
+        )],
+        vec![&owner],
+    )
+    .await
+    .unwrap();
+
     println!("user a: {}", usera.pubkey());
     println!("user b: {}", userb.pubkey());
     let (nft_a, _deposit_a) = test_deposit(
@@ -110,7 +162,7 @@ async fn functionality_test() {
     )
     .await
     .unwrap();
-    let (nft_b, _deposit_b) = test_deposit(
+    let (nft_b, deposit_b) = test_deposit(
         &mut context,
         source,
         x404_state,
@@ -123,6 +175,50 @@ async fn functionality_test() {
     .await
     .unwrap();
 
+    // nft from now on are revealed later, path of each number is committed
+    let reveal_paths = ["1.json", "3.json", "0.json", "2.json"];
+    let (reveal_hash, reveal_proofs) = reveal_tree(&reveal_paths);
+    let set_reveal = |signer: Pubkey| {
+        x404::instructions::set_reveal(
+            PLACEHOLDER_URI.to_string(),
+            reveal_hash,
+            source,
+            hub_state,
+            x404_state,
+            signer,
+        )
+    };
+    // only the manager can set the reveal
+    assert!(execute(
+        &mut context,
+        &usera,
+        &[set_reveal(usera.pubkey())],
+        vec![&usera]
+    )
+    .await
+    .is_err());
+    execute(
+        &mut context,
+        &owner,
+        &[set_reveal(owner.pubkey())],
+        vec![&owner],
+    )
+    .await
+    .unwrap();
+    // the committed paths cannot be replaced
+    let (other_hash, _) = reveal_tree(&["0.json", "1.json", "2.json", "3.json"]);
+    let reset_reveal = x404::instructions::set_reveal(
+        PLACEHOLDER_URI.to_string(),
+        other_hash,
+        source,
+        hub_state,
+        x404_state,
+        owner.pubkey(),
+    );
+    assert!(execute(&mut context, &owner, &[reset_reveal], vec![&owner])
+        .await
+        .is_err());
+
     let (nft_c, deposit_c) = test_deposit(
         &mut context,
         source,
@@ -136,6 +232,113 @@ async fn functionality_test() {
     .await
     .unwrap();
 
+    // issued NFTs carry their own metadata and point to the collection
+    let metadata = read_token_metadata(&mut context, nft_b).await;
+    assert_eq!(metadata.name, "test_nft #1");
+    assert_eq!(metadata.symbol, "test_nft");
+    assert_eq!(
+        metadata.uri,
+        "https://www.google.com/images/branding/googlelogo/1x/googlelogo_light_color_272x92dp.png/1.json"
+    );
+    assert_eq!(metadata.additional_metadata, vec![]);
+    // and are members of the collection group
+    let member = read_mint_extension::<TokenGroupMember>(&mut context, nft_b).await;
+    assert_eq!(member.mint, nft_b);
+    assert_eq!(member.group, collection_mint);
+    assert_eq!(u32::from(member.member_number), 2);
+    let group = read_mint_extension::<TokenGroup>(&mut context, collection_mint).await;
+    assert_eq!(u32::from(group.size), 3);
+    // only issued NFT can be frozen by the state, for soulbound binds
+    assert_eq!(
+        read_mint(&mut context, nft_b).await.freeze_authority,
+        COption::Some(x404_state)
+    );
+    assert_eq!(
+        read_mint(&mut context, collection_mint)
+            .await
+            .freeze_authority,
+        COption::None
+    );
+    // issued in auto bind mode, the state can burn the copies of the NFT
+    let delegate = read_mint_extension::<PermanentDelegate>(&mut context, nft_b).await;
+    assert_eq!(Option::<Pubkey>::from(delegate.delegate), Some(x404_state));
+    // only the x404 state can create the hook accounts of its mints
+    let init_meta_list = Instruction::new_with_bytes(
+        HookID,
+        &x404_hook::instruction::InitializeNftMetaList {}.data(),
+        x404_hook::accounts::InitializeNftMetaList {
+            payer: usera.pubkey(),
+            extra_account_meta_list: get_extra_account_metas_address(&collection_mint, &HookID),
+            mint: collection_mint,
+            state: usera.pubkey(),
+            system_program: solana_sdk::system_program::id(),
+        }
+        .to_account_metas(None),
+    );
+    assert!(
+        execute(&mut context, &usera, &[init_meta_list], vec![&usera])
+            .await
+            .is_err()
+    );
+
+    // nft_c waits for the reveal
+    let metadata = read_token_metadata(&mut context, nft_c).await;
+    assert_eq!(metadata.uri, PLACEHOLDER_URI);
+    let reveal_c = |path: &str| {
+        x404::instructions::reveal_nft(
+            2,
+            path.to_string(),
+            reveal_proofs[2].clone(),
+            source,
+            x404_state,
+            nft_c,
+            usera.pubkey(),
+        )
+    };
+    assert!(execute(
+        &mut context,
+        &usera,
+        &[reveal_c(reveal_paths[2])],
+        vec![&usera]
+    )
+    .await
+    .is_err());
+
+    execute(
+        &mut context,
+        &owner,
+        &[x404::instructions::reveal(
+            REVEAL_BASE_URI.to_string(),
+            source,
+            hub_state,
+            x404_state,
+            owner.pubkey(),
+        )],
+        vec![&owner],
+    )
+    .await
+    .unwrap();
+
+    // anyone can reveal an nft with its proof, the path must match
+    assert!(execute(
+        &mut context,
+        &usera,
+        &[reveal_c(reveal_paths[1])],
+        vec![&usera]
+    )
+    .await
+    .is_err());
+    execute(
+        &mut context,
+        &usera,
+        &[reveal_c(reveal_paths[2])],
+        vec![&usera],
+    )
+    .await
+    .unwrap();
+    let metadata = read_token_metadata(&mut context, nft_c).await;
+    assert_eq!(metadata.uri, format!("{REVEAL_BASE_URI}/0.json"));
+
     println!("check owner store");
     // ======================
     // a: 1000, nft_a
@@ -169,6 +372,27 @@ async fn functionality_test() {
     assert_balance(&mut context, a_balance, FUNGIBLE_SUPPLY).await;
     assert_balance(&mut context, b_balance, FUNGIBLE_SUPPLY * 2).await;
 
+    println!("check view instructions");
+    let owner_view = simulate_view::<OwnerNFTsView>(
+        &mut context,
+        &owner,
+        x404::instructions::get_owner_nfts(source, x404_state, owner_store, userb.pubkey()),
+    )
+    .await;
+    assert_eq!(owner_view.owner, userb.pubkey());
+    assert_eq!(owner_view.nfts, vec![nft_b, nft_c]);
+
+    let nft_bank_c = add_pda(&[b"nft_bank".as_ref(), deposit_c.as_ref()], ID);
+    let bank_view = simulate_view::<BankInfoView>(
+        &mut context,
+        &owner,
+        x404::instructions::get_bank_info(deposit_c, nft_bank_c),
+    )
+    .await;
+    assert_eq!(bank_view.id, deposit_c);
+    assert_eq!(bank_view.owner, userb.pubkey());
+    assert!(bank_view.issued);
+
     // transfer token
     println!("Test Transfer");
     test_transfer(
@@ -211,6 +435,134 @@ async fn functionality_test() {
     assert_eq!(state_data.nft_supply, 3);
     assert_eq!(state_data.nft_in_use, 3);
 
+    let holder_view = simulate_view::<NFTHolderView>(
+        &mut context,
+        &owner,
+        x404::instructions::get_nft_holder(2, source, x404_state, owner_store, nft_c),
+    )
+    .await;
+    assert_eq!(holder_view.mint, nft_c);
+    assert_eq!(holder_view.holder, Some(usera.pubkey()));
+
+    // b cannot bind the nft held by a
+    let bind_receiver =
+        get_associated_token_address_with_program_id(&userb.pubkey(), &nft_c, &spl_token_2022::ID);
+    let invalid_bind = x404::instructions::bind(
+        2,
+        source,
+        x404_state,
+        owner_store,
+        add_pda(&[b"bound".as_ref(), x404_state.as_ref()], ID),
+        nft_c,
+        bind_receiver,
+        fungible_mint,
+        b_balance,
+        userb.pubkey(),
+    );
+    assert!(execute(&mut context, &userb, &[invalid_bind], vec![&userb])
+        .await
+        .is_err());
+
+    // bind and unbind both NFT of a at once
+    let bound_registry = add_pda(&[b"bound".as_ref(), x404_state.as_ref()], ID);
+    let a_nfts: Vec<(Pubkey, Pubkey)> = [nft_a, nft_c]
+        .iter()
+        .map(|nft| {
+            (
+                *nft,
+                get_associated_token_address_with_program_id(
+                    &usera.pubkey(),
+                    nft,
+                    &spl_token_2022::ID,
+                ),
+            )
+        })
+        .collect();
+    // the accounts must follow the numbers
+    let mismatched_bind = x404::instructions::batch_bind(
+        vec![2, 0],
+        &a_nfts,
+        source,
+        x404_state,
+        owner_store,
+        bound_registry,
+        fungible_mint,
+        a_balance,
+        usera.pubkey(),
+    );
+    assert!(
+        execute(&mut context, &usera, &[mismatched_bind], vec![&usera])
+            .await
+            .is_err()
+    );
+    execute(
+        &mut context,
+        &usera,
+        &[x404::instructions::batch_bind(
+            vec![0, 2],
+            &a_nfts,
+            source,
+            x404_state,
+            owner_store,
+            bound_registry,
+            fungible_mint,
+            a_balance,
+            usera.pubkey(),
+        )],
+        vec![&usera],
+    )
+    .await
+    .unwrap();
+    let store = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap();
+    assert_eq!(store.get_map().get(&usera.pubkey()), None);
+    assert_eq!(store.get_balances().get(&usera.pubkey()), None);
+    assert_balance(&mut context, a_balance, 0).await;
+    let registry = read_account::<BoundRegistry>(&mut context, bound_registry)
+        .await
+        .unwrap();
+    for (nft, account) in a_nfts.iter() {
+        assert_eq!(registry.holder(nft), Some(usera.pubkey()));
+        assert_balance(&mut context, *account, 1).await;
+    }
+    execute(
+        &mut context,
+        &usera,
+        &[x404::instructions::batch_unbind(
+            vec![0, 2],
+            &a_nfts,
+            source,
+            x404_state,
+            owner_store,
+            bound_registry,
+            add_pda(&[b"exemption".as_ref(), x404_state.as_ref()], ID),
+            holder_config(x404_state, usera.pubkey()),
+            fungible_mint,
+            a_balance,
+            usera.pubkey(),
+        )],
+        vec![&usera],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        read_account::<OwnerStore>(&mut context, owner_store)
+            .await
+            .unwrap()
+            .get_map()
+            .get(&usera.pubkey())
+            .unwrap()
+            .to_owned(),
+        vec![nft_a, nft_c]
+    );
+    assert_balance(&mut context, a_balance, FUNGIBLE_SUPPLY * 2).await;
+    assert!(read_account::<BoundRegistry>(&mut context, bound_registry)
+        .await
+        .unwrap()
+        .nfts
+        .is_empty());
+
     // bind then transfer
     test_bind(
         &mut context,
@@ -246,50 +598,132 @@ async fn functionality_test() {
     assert_balance(&mut context, a_balance, FUNGIBLE_SUPPLY).await;
     assert_balance(&mut context, b_balance, FUNGIBLE_SUPPLY).await;
 
-    test_transfer(
+    let holder_view = simulate_view::<NFTHolderView>(
         &mut context,
-        &usera,
-        &userb.pubkey(),
-        fungible_mint,
-        extra_account,
-        x404_state,
-        owner_store,
-        FUNGIBLE_SUPPLY / 2,
+        &owner,
+        x404::instructions::get_nft_holder(2, source, x404_state, owner_store, nft_c),
+    )
+    .await;
+    assert_eq!(holder_view.holder, None);
+
+    // once an allowlist is set, bound NFT only move through its marketplaces
+    let set_royalty = |marketplaces: Vec<Pubkey>| {
+        x404::instructions::set_royalty(
+            500,
+            owner.pubkey(),
+            marketplaces,
+            source,
+            hub_state,
+            x404_state,
+            collection_mint,
+            owner.pubkey(),
+        )
+    };
+    execute(
+        &mut context,
+        &owner,
+        &[set_royalty(vec![marketplace.pubkey()])],
+        vec![&owner],
     )
     .await
     .unwrap();
-    // ======================
-    // a:      500,
-    // b:     1500, nft_b,
-    // state:     , nft_a
-    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+    let state_data = read_account::<X404State>(&mut context, x404_state)
         .await
-        .unwrap()
-        .get_map();
-
+        .unwrap();
+    assert_eq!(state_data.royalty_bps, 500);
+    assert_eq!(state_data.marketplaces, vec![marketplace.pubkey()]);
+    let collection_metadata = read_token_metadata(&mut context, collection_mint).await;
     assert_eq!(
-        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
-        vec![]
+        collection_metadata.additional_metadata,
+        vec![
+            ("royalty_bps".to_string(), "500".to_string()),
+            ("royalty_receiver".to_string(), owner.pubkey().to_string()),
+        ]
     );
 
-    assert_eq!(
-        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
-        vec![nft_b]
-    );
-    assert_eq!(
-        owner_store_data.get(&x404_state).unwrap().to_owned(),
-        vec![nft_a]
-    );
+    let nft_c_account =
+        get_associated_token_address_with_program_id(&usera.pubkey(), &nft_c, &spl_token_2022::ID);
+    let nft_c_second = create_token_account(&mut context, &usera, usera.pubkey(), nft_c)
+        .await
+        .unwrap();
+    // moving it between accounts of the holder is not a sale
+    for (from, to) in [(nft_c_account, nft_c_second), (nft_c_second, nft_c_account)] {
+        test_nft_transfer(
+            &mut context,
+            &usera,
+            &usera,
+            from,
+            to,
+            nft_c,
+            x404_state,
+            owner_store,
+        )
+        .await
+        .unwrap();
+    }
+    let nft_c_b = create_token_account(&mut context, &userb, userb.pubkey(), nft_c)
+        .await
+        .unwrap();
+    assert!(test_nft_transfer(
+        &mut context,
+        &usera,
+        &usera,
+        nft_c_account,
+        nft_c_b,
+        nft_c,
+        x404_state,
+        owner_store,
+    )
+    .await
+    .is_err());
+    for (holder, from, to) in [
+        (&usera, nft_c_account, nft_c_b),
+        (&userb, nft_c_b, nft_c_account),
+    ] {
+        let approve = spl_token_2022::instruction::approve(
+            &spl_token_2022::ID,
+            &from,
+            &marketplace.pubkey(),
+            &holder.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap();
+        execute(&mut context, holder, &[approve], vec![holder])
+            .await
+            .unwrap();
+        test_nft_transfer(
+            &mut context,
+            &marketplace,
+            holder,
+            from,
+            to,
+            nft_c,
+            x404_state,
+            owner_store,
+        )
+        .await
+        .unwrap();
+    }
+    assert_balance(&mut context, nft_c_account, 1).await;
+    execute(&mut context, &owner, &[set_royalty(vec![])], vec![&owner])
+        .await
+        .unwrap();
 
-    assert_balance(&mut context, a_balance, FUNGIBLE_SUPPLY / 2).await;
-    assert_balance(
+    // the holder must unbind from the account holding the NFT
+    test_nft_transfer(
         &mut context,
-        b_balance,
-        FUNGIBLE_SUPPLY / 2 + FUNGIBLE_SUPPLY,
+        &usera,
+        &usera,
+        nft_c_account,
+        nft_c_second,
+        nft_c,
+        x404_state,
+        owner_store,
     )
-    .await;
-    // unbind
-    test_unbind(
+    .await
+    .unwrap();
+    assert!(test_unbind(
         &mut context,
         source,
         x404_state,
@@ -300,93 +734,1228 @@ async fn functionality_test() {
         2,
     )
     .await
+    .is_err());
+    test_nft_transfer(
+        &mut context,
+        &usera,
+        &usera,
+        nft_c_second,
+        nft_c_account,
+        nft_c,
+        x404_state,
+        owner_store,
+    )
+    .await
     .unwrap();
-    // ======================
-    // a:     1500, nft_c
-    // b:     1500, nft_b,
-    // state:     , nft_a
-    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
-        .await
-        .unwrap()
-        .get_map();
-
-    assert_eq!(
-        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
-        vec![nft_c]
-    );
 
+    // the unbind right follows the NFT to its new owner
     assert_eq!(
-        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
-        vec![nft_b]
+        read_account::<BoundRegistry>(&mut context, bound_registry)
+            .await
+            .unwrap()
+            .holder(&nft_c),
+        Some(usera.pubkey())
     );
+    test_nft_transfer(
+        &mut context,
+        &usera,
+        &usera,
+        nft_c_account,
+        nft_c_b,
+        nft_c,
+        x404_state,
+        owner_store,
+    )
+    .await
+    .unwrap();
     assert_eq!(
-        owner_store_data.get(&x404_state).unwrap().to_owned(),
-        vec![nft_a]
+        read_account::<BoundRegistry>(&mut context, bound_registry)
+            .await
+            .unwrap()
+            .holder(&nft_c),
+        Some(userb.pubkey())
     );
-
-    assert_balance(
+    assert!(test_unbind(
         &mut context,
-        a_balance,
-        FUNGIBLE_SUPPLY + FUNGIBLE_SUPPLY / 2,
+        source,
+        x404_state,
+        owner_store,
+        nft_c,
+        &usera,
+        fungible_mint,
+        2,
+    )
+    .await
+    .is_err());
+    // only the hook may move the unbind right
+    let forged_transfer = x404::instructions::transfer_bound(
+        x404_state,
+        bound_registry,
+        nft_c,
+        nft_c_b,
+        nft_c_account,
+        userb.pubkey(),
+    );
+    assert!(
+        execute(&mut context, &userb, &[forged_transfer], vec![&userb])
+            .await
+            .is_err()
+    );
+    test_nft_transfer(
+        &mut context,
+        &userb,
+        &userb,
+        nft_c_b,
+        nft_c_account,
+        nft_c,
+        x404_state,
+        owner_store,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        read_account::<BoundRegistry>(&mut context, bound_registry)
+            .await
+            .unwrap()
+            .holder(&nft_c),
+        Some(usera.pubkey())
+    );
+
+    test_transfer(
+        &mut context,
+        &usera,
+        &userb.pubkey(),
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY / 2,
+    )
+    .await
+    .unwrap();
+    // ======================
+    // a:      500,
+    // b:     1500, nft_b,
+    // state:     , nft_a
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(owner_store_data.get(&usera.pubkey()), None);
+
+    assert_eq!(
+        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a]
+    );
+
+    assert_balance(&mut context, a_balance, FUNGIBLE_SUPPLY / 2).await;
+    assert_balance(
+        &mut context,
+        b_balance,
+        FUNGIBLE_SUPPLY / 2 + FUNGIBLE_SUPPLY,
+    )
+    .await;
+    // unbind
+    test_unbind(
+        &mut context,
+        source,
+        x404_state,
+        owner_store,
+        nft_c,
+        &usera,
+        fungible_mint,
+        2,
+    )
+    .await
+    .unwrap();
+    // ======================
+    // a:     1500, nft_c
+    // b:     1500, nft_b,
+    // state:     , nft_a
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(
+        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
+        vec![nft_c]
+    );
+
+    assert_eq!(
+        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a]
+    );
+
+    assert_balance(
+        &mut context,
+        a_balance,
+        FUNGIBLE_SUPPLY + FUNGIBLE_SUPPLY / 2,
+    )
+    .await;
+    assert_balance(
+        &mut context,
+        b_balance,
+        FUNGIBLE_SUPPLY / 2 + FUNGIBLE_SUPPLY,
+    )
+    .await;
+    // the injected metadata account is not part of the warped bank hash
+    context.warp_to_epoch(2).unwrap();
+
+    // mirror the deposited metadata on nft_c
+    let deposit_uri = "https://example.com/deposit_c.json";
+    let deposit_metadata = add_deposit_metadata(&mut context, deposit_c, deposit_uri);
+    let deposit_c_receiver =
+        get_associated_token_address_with_program_id(&x404_state, &deposit_c, &spl_token::ID);
+    let link = |signer: Pubkey, deposit_receiver: Pubkey| {
+        x404::instructions::link_deposit(
+            2,
+            source,
+            x404_state,
+            nft_bank_c,
+            deposit_c,
+            deposit_receiver,
+            deposit_metadata,
+            nft_c,
+            signer,
+        )
+    };
+    // only the state owner can link
+    assert!(execute(
+        &mut context,
+        &usera,
+        &[link(usera.pubkey(), deposit_c_receiver)],
+        vec![&usera]
+    )
+    .await
+    .is_err());
+    // and only a deposit held by the state
+    let deposit_b_receiver =
+        get_associated_token_address_with_program_id(&x404_state, &deposit_b, &spl_token::ID);
+    assert!(execute(
+        &mut context,
+        &owner,
+        &[link(owner.pubkey(), deposit_b_receiver)],
+        vec![&owner]
+    )
+    .await
+    .is_err());
+    execute(
+        &mut context,
+        &owner,
+        &[link(owner.pubkey(), deposit_c_receiver)],
+        vec![&owner],
+    )
+    .await
+    .unwrap();
+    let metadata = read_token_metadata(&mut context, nft_c).await;
+    assert_eq!(metadata.uri, deposit_uri);
+    for field in [
+        ("deposit".to_string(), deposit_c.to_string()),
+        ("deposit_name".to_string(), "deposit".to_string()),
+        ("deposit_symbol".to_string(), "DEP".to_string()),
+    ] {
+        assert!(metadata.additional_metadata.contains(&field));
+    }
+    let bank_view = simulate_view::<BankInfoView>(
+        &mut context,
+        &owner,
+        x404::instructions::get_bank_info(deposit_c, nft_bank_c),
+    )
+    .await;
+    assert_eq!(bank_view.linked_nft, nft_c);
+
+    // redeem
+    test_redeem(
+        &mut context,
+        source,
+        x404_state,
+        owner_store,
+        deposit_c,
+        &usera,
+        fungible_mint,
+        userb.pubkey(),
+        Some(nft_c),
+    )
+    .await
+    .unwrap();
+
+    // the redeemed deposit no longer shapes nft_c
+    let metadata = read_token_metadata(&mut context, nft_c).await;
+    assert_eq!(metadata.name, "test_nft #2");
+    assert_eq!(metadata.uri, format!("{REVEAL_BASE_URI}/0.json"));
+    assert_eq!(metadata.additional_metadata, vec![]);
+
+    // ======================
+    // a:     400,
+    // b:     1600, nft_b,
+    // state:     , nft_a,nft_c
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(owner_store_data.get(&usera.pubkey()), None);
+
+    assert_eq!(
+        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a, nft_c]
+    );
+
+    assert_balance(&mut context, a_balance, FUNGIBLE_SUPPLY / 2 - REDEEMFEE).await;
+    assert_balance(
+        &mut context,
+        b_balance,
+        FUNGIBLE_SUPPLY / 2 + FUNGIBLE_SUPPLY + REDEEMFEE,
     )
     .await;
+
+    test_transfer(
+        &mut context,
+        &userb,
+        &usera.pubkey(),
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY,
+    )
+    .await
+    .unwrap();
+
+    // ======================
+    // a:     1400, nft_b
+    // b:      600,
+    // state:     , nft_a,nft_c
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(
+        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+
+    assert_eq!(owner_store_data.get(&userb.pubkey()), None);
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a, nft_c]
+    );
+
     assert_balance(
         &mut context,
-        b_balance,
-        FUNGIBLE_SUPPLY / 2 + FUNGIBLE_SUPPLY,
+        a_balance,
+        FUNGIBLE_SUPPLY + FUNGIBLE_SUPPLY / 2 - REDEEMFEE,
+    )
+    .await;
+    assert_balance(&mut context, b_balance, FUNGIBLE_SUPPLY / 2 + REDEEMFEE).await;
+
+    let state_data = read_account::<X404State>(&mut context, x404_state)
+        .await
+        .unwrap();
+
+    assert_eq!(state_data.nft_supply, 3);
+    assert_eq!(state_data.nft_in_use, 2);
+
+    // move tokens to another account of a, nft stays with a
+    let a_second = create_token_account(&mut context, &usera, usera.pubkey(), fungible_mint)
+        .await
+        .unwrap();
+    // the hook has no payer, a store out of spare lamports can't record the new account
+    assert!(test_transfer_between(
+        &mut context,
+        &usera,
+        a_balance,
+        a_second,
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY,
+    )
+    .await
+    .is_err());
+    // anyone can top it up with a plain transfer
+    transfer_lamports(&mut context, &owner, owner_store, 1_000_000_000)
+        .await
+        .unwrap();
+    let slot = context.banks_client.get_root_slot().await.unwrap();
+    context.warp_to_slot(slot + 1).unwrap();
+    test_transfer_between(
+        &mut context,
+        &usera,
+        a_balance,
+        a_second,
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY,
+    )
+    .await
+    .unwrap();
+
+    // ======================
+    // a:     400 + 1000, nft_b
+    // b:      600,
+    // state:     , nft_a,nft_c
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(
+        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a, nft_c]
+    );
+    assert_balance(&mut context, a_balance, FUNGIBLE_SUPPLY / 2 - REDEEMFEE).await;
+    assert_balance(&mut context, a_second, FUNGIBLE_SUPPLY).await;
+
+    // transfer from the non associated account, nft follows the owner
+    test_transfer_between(
+        &mut context,
+        &usera,
+        a_second,
+        b_balance,
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY,
+    )
+    .await
+    .unwrap();
+
+    // ======================
+    // a:     400 + 0,
+    // b:     1600, nft_b
+    // state:     , nft_a,nft_c
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(owner_store_data.get(&usera.pubkey()), None);
+    assert_eq!(
+        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_balance(&mut context, a_second, 0).await;
+    assert_balance(
+        &mut context,
+        b_balance,
+        FUNGIBLE_SUPPLY + FUNGIBLE_SUPPLY / 2 + REDEEMFEE,
+    )
+    .await;
+
+    // exempt b, its nft goes to the pool
+    test_exemption(
+        &mut context,
+        &owner,
+        source,
+        hub_state,
+        x404_state,
+        userb.pubkey(),
+        true,
+    )
+    .await
+    .unwrap();
+
+    // ======================
+    // a:     400,
+    // b:     1600 (exempted),
+    // state:     , nft_a,nft_c,nft_b
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(owner_store_data.get(&userb.pubkey()), None);
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a, nft_c, nft_b]
+    );
+
+    // exempted sender, the nft comes from the pool
+    test_transfer(
+        &mut context,
+        &userb,
+        &usera.pubkey(),
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY,
+    )
+    .await
+    .unwrap();
+
+    // ======================
+    // a:     1400, nft_b
+    // b:     600 (exempted),
+    // state:     , nft_a,nft_c
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(
+        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_eq!(owner_store_data.get(&userb.pubkey()), None);
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a, nft_c]
+    );
+
+    // exempted receiver, the nft goes to the pool
+    test_transfer(
+        &mut context,
+        &usera,
+        &userb.pubkey(),
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY,
+    )
+    .await
+    .unwrap();
+
+    // ======================
+    // a:     400,
+    // b:     1600 (exempted),
+    // state:     , nft_a,nft_c,nft_b
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(owner_store_data.get(&usera.pubkey()), None);
+    assert_eq!(owner_store_data.get(&userb.pubkey()), None);
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a, nft_c, nft_b]
+    );
+
+    // only the manager can update exemptions
+    let exemption = add_pda(&[b"exemption".as_ref(), x404_state.as_ref()], ID);
+    let invalid_exemption = x404::instructions::remove_exemption(
+        userb.pubkey(),
+        source,
+        hub_state,
+        x404_state,
+        owner_store,
+        exemption,
+        holder_config(x404_state, userb.pubkey()),
+        userb.pubkey(),
+    );
+    assert!(
+        execute(&mut context, &userb, &[invalid_exemption], vec![&userb])
+            .await
+            .is_err()
+    );
+
+    // only the manager can update the fungible metadata
+    let invalid_metadata = x404::instructions::update_metadata(
+        FUNGIBLE_NAME.to_string(),
+        FUNGIBLE_SYMBOL.to_string(),
+        FUNGIBLE_URI.to_string(),
+        source,
+        hub_state,
+        x404_state,
+        fungible_mint,
+        userb.pubkey(),
+    );
+    assert!(
+        execute(&mut context, &userb, &[invalid_metadata], vec![&userb])
+            .await
+            .is_err()
+    );
+
+    // only the manager can approve hook programs
+    let invalid_hook = x404::instructions::remove_hook_program(HookID, hub_state, userb.pubkey());
+    assert!(execute(&mut context, &userb, &[invalid_hook], vec![&userb])
+        .await
+        .is_err());
+
+    // revoking a program shrinks the hub and refunds the rent it no longer needs
+    let extra_program = Pubkey::new_unique();
+    let add_extra = x404::instructions::add_hook_program(extra_program, hub_state, owner.pubkey());
+    execute(&mut context, &owner, &[add_extra], vec![&owner])
+        .await
+        .unwrap();
+    let remove_extra =
+        x404::instructions::remove_hook_program(extra_program, hub_state, owner.pubkey());
+    execute(&mut context, &owner, &[remove_extra], vec![&owner])
+        .await
+        .unwrap();
+    let hub_account = context
+        .banks_client
+        .get_account(hub_state)
+        .await
+        .unwrap()
+        .unwrap();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    assert_eq!(
+        hub_account.lamports,
+        rent.minimum_balance(hub_account.data.len())
+    );
+
+    // only the state owner can rewrite the hook meta list
+    let invalid_update =
+        update_extra_account(extra_account, fungible_mint, x404_state, userb.pubkey());
+    assert!(
+        execute(&mut context, &userb, &[invalid_update], vec![&userb])
+            .await
+            .is_err()
+    );
+    execute(
+        &mut context,
+        &owner,
+        &[update_extra_account(
+            extra_account,
+            fungible_mint,
+            x404_state,
+            owner.pubkey(),
+        )],
+        vec![&owner],
+    )
+    .await
+    .unwrap();
+
+    // NFT meta lists created before the bound registry was added get it back
+    let nft_meta_list = get_extra_account_metas_address(&nft_b, &HookID);
+    let meta_list_account = context
+        .banks_client
+        .get_account(nft_meta_list)
+        .await
+        .unwrap()
+        .unwrap();
+    // 8 bytes discriminator, 4 bytes length, 4 bytes count, 35 bytes per meta
+    let mut legacy_data = meta_list_account.data[..8].to_vec();
+    legacy_data.extend_from_slice(&(4 + 3 * 35u32).to_le_bytes());
+    legacy_data.extend_from_slice(&3u32.to_le_bytes());
+    legacy_data.extend_from_slice(&meta_list_account.data[16..16 + 3 * 35]);
+    let mut legacy_meta_list =
+        AccountSharedData::new(meta_list_account.lamports, legacy_data.len(), &HookID);
+    legacy_meta_list.set_data_from_slice(&legacy_data);
+    context.set_account(&nft_meta_list, &legacy_meta_list);
+    execute(
+        &mut context,
+        &owner,
+        &[update_extra_account(
+            nft_meta_list,
+            nft_b,
+            x404_state,
+            owner.pubkey(),
+        )],
+        vec![&owner],
+    )
+    .await
+    .unwrap();
+    let migrated_meta_list = context
+        .banks_client
+        .get_account(nft_meta_list)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(migrated_meta_list.data, meta_list_account.data);
+    // the collection mint has no meta list to update
+    assert!(execute(
+        &mut context,
+        &owner,
+        &[update_extra_account(
+            get_extra_account_metas_address(&collection_mint, &HookID),
+            collection_mint,
+            x404_state,
+            owner.pubkey(),
+        )],
+        vec![&owner],
+    )
+    .await
+    .is_err());
+
+    // remove exemption, b claims back from the pool
+    test_exemption(
+        &mut context,
+        &owner,
+        source,
+        hub_state,
+        x404_state,
+        userb.pubkey(),
+        false,
+    )
+    .await
+    .unwrap();
+
+    // ======================
+    // a:     400,
+    // b:     1600, nft_b
+    // state:     , nft_a,nft_c
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(
+        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a, nft_c]
+    );
+
+    // b only wants the fungible side
+    test_skip_nft(&mut context, source, x404_state, &userb, true)
+        .await
+        .unwrap();
+
+    // ======================
+    // a:     400,
+    // b:     1600 (skip nft),
+    // state:     , nft_a,nft_c,nft_b
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(owner_store_data.get(&userb.pubkey()), None);
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a, nft_c, nft_b]
+    );
+
+    test_transfer(
+        &mut context,
+        &userb,
+        &usera.pubkey(),
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY,
+    )
+    .await
+    .unwrap();
+
+    // ======================
+    // a:     1400, nft_b
+    // b:     600 (skip nft),
+    // state:     , nft_a,nft_c
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(
+        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_eq!(owner_store_data.get(&userb.pubkey()), None);
+
+    test_transfer(
+        &mut context,
+        &usera,
+        &userb.pubkey(),
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY,
+    )
+    .await
+    .unwrap();
+
+    // revert the flag, b claims back from the pool
+    test_skip_nft(&mut context, source, x404_state, &userb, false)
+        .await
+        .unwrap();
+
+    // ======================
+    // a:     400,
+    // b:     1600, nft_b
+    // state:     , nft_a,nft_c
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(owner_store_data.get(&usera.pubkey()), None);
+    assert_eq!(
+        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_eq!(
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+        vec![nft_a, nft_c]
+    );
+
+    // a moves b's tokens as a delegate, the NFT still follows the owners
+    test_delegated_transfer(
+        &mut context,
+        &userb,
+        &usera,
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        FUNGIBLE_SUPPLY,
+    )
+    .await
+    .unwrap();
+
+    // ======================
+    // a:     1400, nft_b
+    // b:     600,
+    // state:     , nft_a,nft_c
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+
+    assert_eq!(
+        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
+        vec![nft_b]
+    );
+    assert_eq!(owner_store_data.get(&userb.pubkey()), None);
+    assert_eq!(
+        read_account::<OwnerStore>(&mut context, owner_store)
+            .await
+            .unwrap()
+            .get_balances()
+            .get(&userb.pubkey())
+            .copied(),
+        Some(600)
+    );
+
+    let summary = simulate_view::<StateSummaryView>(
+        &mut context,
+        &owner,
+        x404::instructions::get_state_summary(source, x404_state),
+    )
+    .await;
+    assert_eq!(summary.fungible_mint, fungible_mint);
+    assert_eq!(summary.nft_supply, state_data.nft_supply);
+    assert_eq!(summary.nft_in_use, state_data.nft_in_use);
+
+    let audit = simulate_view::<AuditView>(
+        &mut context,
+        &owner,
+        x404::instructions::audit_state(
+            source,
+            x404_state,
+            owner_store,
+            bound_registry,
+            fungible_mint,
+        ),
+    )
+    .await;
+    assert_eq!(audit.discrepancies, vec![]);
+    assert_eq!(audit.nft_supply, state_data.nft_supply);
+    assert_eq!(audit.assigned + audit.bound, state_data.nft_supply);
+    assert_eq!(audit.balances, audit.fungible_mint_supply);
+
+    // a rerolls nft_b for a pool mint, paying the fee to the state owner
+    let set_reroll = |randomness: Pubkey| {
+        x404::instructions::set_reroll(
+            REROLL_FEE,
+            randomness,
+            source,
+            hub_state,
+            x404_state,
+            owner.pubkey(),
+        )
+    };
+    // the draw is settled from the hash of the request slot, no other source
+    for randomness in [Pubkey::default(), Pubkey::new_unique()] {
+        assert!(execute(
+            &mut context,
+            &owner,
+            &[set_reroll(randomness)],
+            vec![&owner]
+        )
+        .await
+        .is_err());
+    }
+    execute(
+        &mut context,
+        &owner,
+        &[set_reroll(sysvar::slot_hashes::ID)],
+        vec![&owner],
+    )
+    .await
+    .unwrap();
+    let request = add_pda(
+        &[
+            b"reroll".as_ref(),
+            x404_state.as_ref(),
+            usera.pubkey().as_ref(),
+        ],
+        ID,
+    );
+    let reroll = |nft_mint: Pubkey| {
+        x404::instructions::reroll(
+            nft_mint,
+            source,
+            x404_state,
+            owner_store,
+            add_pda(&[b"exemption".as_ref(), x404_state.as_ref()], ID),
+            holder_config(x404_state, usera.pubkey()),
+            holder_config(x404_state, owner.pubkey()),
+            fungible_mint,
+            a_balance,
+            get_associated_token_address_with_program_id(
+                &owner.pubkey(),
+                &fungible_mint,
+                &spl_token_2022::ID,
+            ),
+            owner.pubkey(),
+            request,
+            usera.pubkey(),
+        )
+    };
+    // anyone can settle the request of a
+    let settle = x404::instructions::settle_reroll(
+        usera.pubkey(),
+        source,
+        x404_state,
+        owner_store,
+        request,
+        userb.pubkey(),
+    );
+    // only assigned mints can be rerolled
+    assert!(
+        execute(&mut context, &usera, &[reroll(nft_a)], vec![&usera])
+            .await
+            .is_err()
+    );
+    execute(&mut context, &usera, &[reroll(nft_b)], vec![&usera])
+        .await
+        .unwrap();
+    assert_balance(&mut context, a_balance, FUNGIBLE_SUPPLY + 400 - REROLL_FEE).await;
+    // the hash of the request slot is not known before the next slot
+    assert!(execute(
+        &mut context,
+        &userb,
+        std::slice::from_ref(&settle),
+        vec![&userb]
+    )
+    .await
+    .is_err());
+    let slot = context
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .slot;
+    context.warp_to_slot(slot + 1).unwrap();
+    // one pending request per holder
+    assert!(
+        execute(&mut context, &usera, &[reroll(nft_b)], vec![&usera])
+            .await
+            .is_err()
+    );
+    execute(
+        &mut context,
+        &userb,
+        std::slice::from_ref(&settle),
+        vec![&userb],
+    )
+    .await
+    .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(request)
+        .await
+        .unwrap()
+        .is_none());
+
+    // ======================
+    // a:     1300, one of nft_a,nft_b,nft_c
+    // b:     600,
+    // owner: 100,
+    // state:     , the two others
+    let store = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap();
+    let owner_store_data = store.get_map();
+    let a_nfts = owner_store_data.get(&usera.pubkey()).unwrap().to_owned();
+    let mut all = [
+        a_nfts.clone(),
+        owner_store_data.get(&x404_state).unwrap().to_owned(),
+    ]
+    .concat();
+    all.sort();
+    let mut expected = vec![nft_a, nft_b, nft_c];
+    expected.sort();
+    assert_eq!(a_nfts.len(), 1);
+    assert_eq!(all, expected);
+    assert_eq!(store.get_index().get(&a_nfts[0]), Some(&usera.pubkey()));
+    assert_eq!(
+        store.get_balances().get(&owner.pubkey()).copied(),
+        Some(REROLL_FEE)
+    );
+
+    // the draw follows the hash of the request slot
+    execute(&mut context, &usera, &[reroll(a_nfts[0])], vec![&usera])
+        .await
+        .unwrap();
+    let request_slot = read_account::<RerollRequest>(&mut context, request)
+        .await
+        .unwrap()
+        .slot;
+    context.warp_to_slot(request_slot + 1).unwrap();
+    let slot_hash = context
+        .banks_client
+        .get_sysvar::<SlotHashes>()
+        .await
+        .unwrap()
+        .get(&request_slot)
+        .unwrap()
+        .to_bytes();
+    let pool = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map()
+        .get(&x404_state)
+        .unwrap()
+        .to_owned();
+    let drawn = pool[reroll_pick(slot_hash, usera.pubkey(), a_nfts[0], pool.len())];
+    execute(&mut context, &userb, &[settle], vec![&userb])
+        .await
+        .unwrap();
+    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map();
+    assert_eq!(
+        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
+        vec![drawn]
+    );
+    assert!(owner_store_data
+        .get(&x404_state)
+        .unwrap()
+        .contains(&a_nfts[0]));
+
+    // soulbound state, a bound NFT is frozen until unbound
+    execute(
+        &mut context,
+        &owner,
+        &[x404::instructions::set_soulbound(
+            true,
+            source,
+            hub_state,
+            x404_state,
+            owner.pubkey(),
+        )],
+        vec![&owner],
+    )
+    .await
+    .unwrap();
+    let number = [nft_a, nft_b, nft_c]
+        .iter()
+        .position(|nft| *nft == drawn)
+        .unwrap() as u64;
+    test_bind(
+        &mut context,
+        source,
+        x404_state,
+        owner_store,
+        drawn,
+        &usera,
+        fungible_mint,
+        number,
+    )
+    .await
+    .unwrap();
+    let bound_account =
+        get_associated_token_address_with_program_id(&usera.pubkey(), &drawn, &spl_token_2022::ID);
+    assert!(read_account::<TokenAccount>(&mut context, bound_account)
+        .await
+        .unwrap()
+        .is_frozen());
+    let other_account = create_token_account(&mut context, &usera, usera.pubkey(), drawn)
+        .await
+        .unwrap();
+    assert!(test_nft_transfer(
+        &mut context,
+        &usera,
+        &usera,
+        bound_account,
+        other_account,
+        drawn,
+        x404_state,
+        owner_store,
     )
-    .await;
-    // redeem
-
-    context.warp_to_epoch(2).unwrap();
-    test_redeem(
+    .await
+    .is_err());
+    test_unbind(
         &mut context,
         source,
         x404_state,
         owner_store,
-        deposit_c,
+        drawn,
         &usera,
         fungible_mint,
-        userb.pubkey(),
+        number,
     )
     .await
     .unwrap();
+    assert_eq!(
+        read_account::<OwnerStore>(&mut context, owner_store)
+            .await
+            .unwrap()
+            .get_map()
+            .get(&usera.pubkey())
+            .unwrap()
+            .to_owned(),
+        vec![drawn]
+    );
 
-    // ======================
-    // a:     400,
-    // b:     1600, nft_b,
-    // state:     , nft_a,nft_c
-    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+    // auto bind mirrors the assignments as frozen copies in the holder wallets
+    let sync_auto_bind = |nfts: &[(Pubkey, Pubkey)]| {
+        x404::instructions::sync_auto_bind(nfts, source, x404_state, owner_store)
+    };
+    execute(
+        &mut context,
+        &usera,
+        &[sync_auto_bind(&[(drawn, other_account)])],
+        vec![&usera],
+    )
+    .await
+    .unwrap();
+    let copy = read_account::<TokenAccount>(&mut context, other_account)
+        .await
+        .unwrap();
+    assert_eq!(copy.amount, 1);
+    assert!(copy.is_frozen());
+
+    // a transfer only moves the virtual assignment, the copies follow on sync
+    let a_amount = read_account::<TokenAccount>(&mut context, a_balance)
         .await
         .unwrap()
-        .get_map();
+        .amount;
+    test_transfer(
+        &mut context,
+        &usera,
+        &userb.pubkey(),
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        a_amount,
+    )
+    .await
+    .unwrap();
+    assert_balance(&mut context, other_account, 1).await;
+    let holder = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_index()
+        .get(&drawn)
+        .copied();
+    assert_eq!(holder, Some(userb.pubkey()));
 
-    assert_eq!(
-        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
-        vec![]
+    let create_copy_account = create_associated_token_account(
+        &userb.pubkey(),
+        &userb.pubkey(),
+        &drawn,
+        &spl_token_2022::ID,
     );
+    execute(&mut context, &userb, &[create_copy_account], vec![&userb])
+        .await
+        .unwrap();
+    let b_copy =
+        get_associated_token_address_with_program_id(&userb.pubkey(), &drawn, &spl_token_2022::ID);
+    execute(
+        &mut context,
+        &userb,
+        &[sync_auto_bind(&[(drawn, other_account), (drawn, b_copy)])],
+        vec![&userb],
+    )
+    .await
+    .unwrap();
+    // the copy is burned from a and minted frozen to b
+    assert_balance(&mut context, other_account, 0).await;
+    let copy = read_account::<TokenAccount>(&mut context, b_copy)
+        .await
+        .unwrap();
+    assert_eq!(copy.amount, 1);
+    assert!(copy.is_frozen());
 
+    // binding takes over the copy instead of minting a second token
+    test_bind(
+        &mut context,
+        source,
+        x404_state,
+        owner_store,
+        drawn,
+        &userb,
+        fungible_mint,
+        number,
+    )
+    .await
+    .unwrap();
+    assert_balance(&mut context, b_copy, 1).await;
     assert_eq!(
-        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
-        vec![nft_b]
-    );
-    assert_eq!(
-        owner_store_data.get(&x404_state).unwrap().to_owned(),
-        vec![nft_a, nft_c]
+        read_account::<BoundRegistry>(&mut context, bound_registry)
+            .await
+            .unwrap()
+            .holder(&drawn),
+        Some(userb.pubkey())
     );
 
-    assert_balance(&mut context, a_balance, FUNGIBLE_SUPPLY / 2 - REDEEMFEE).await;
-    assert_balance(
+    // a deposit reusing a pool mint counts once in nft_in_use
+    let state_data = read_account::<X404State>(&mut context, x404_state)
+        .await
+        .unwrap();
+    assert!(state_data.nft_supply > state_data.nft_in_use);
+    test_deposit(
         &mut context,
-        b_balance,
-        FUNGIBLE_SUPPLY / 2 + FUNGIBLE_SUPPLY + REDEEMFEE,
+        source,
+        x404_state,
+        owner_store,
+        &owner,
+        &usera,
+        fungible_mint,
+        state_data.nft_supply,
     )
-    .await;
+    .await
+    .unwrap();
+    let reused = read_account::<X404State>(&mut context, x404_state)
+        .await
+        .unwrap();
+    assert_eq!(reused.nft_supply, state_data.nft_supply);
+    assert_eq!(reused.nft_in_use, state_data.nft_in_use + 1);
 
-    test_transfer(
+    // an owner change never reaches the hook, syncing the account moves the
+    // balance and the NFT it is worth to the new owner
+    test_transfer_between(
         &mut context,
-        &userb,
-        &usera.pubkey(),
+        &usera,
+        a_balance,
+        a_second,
         fungible_mint,
         extra_account,
         x404_state,
@@ -395,44 +1964,260 @@ async fn functionality_test() {
     )
     .await
     .unwrap();
-
-    // ======================
-    // a:     1400, nft_b
-    // b:      600,
-    // state:     , nft_a,nft_c
-    let owner_store_data = read_account::<OwnerStore>(&mut context, owner_store)
+    let b_before = read_account::<OwnerStore>(&mut context, owner_store)
         .await
         .unwrap()
-        .get_map();
-
+        .get_balances()
+        .get(&userb.pubkey())
+        .copied()
+        .unwrap_or(0);
+    let change_owner = spl_token_2022::instruction::set_authority(
+        &spl_token_2022::ID,
+        &a_second,
+        Some(&userb.pubkey()),
+        spl_token_2022::instruction::AuthorityType::AccountOwner,
+        &usera.pubkey(),
+        &[],
+    )
+    .unwrap();
+    execute(&mut context, &usera, &[change_owner], vec![&usera])
+        .await
+        .unwrap();
+    execute(
+        &mut context,
+        &userb,
+        &[x404::instructions::sync_balance(
+            source,
+            x404_state,
+            owner_store,
+            add_pda(&[b"exemption".as_ref(), x404_state.as_ref()], ID),
+            holder_config(x404_state, userb.pubkey()),
+            fungible_mint,
+            a_second,
+            userb.pubkey(),
+        )],
+        vec![&userb],
+    )
+    .await
+    .unwrap();
+    let store = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap();
+    assert_eq!(store.get_balances().get(&usera.pubkey()), None);
+    assert_eq!(store.get_map().get(&usera.pubkey()), None);
     assert_eq!(
-        owner_store_data.get(&usera.pubkey()).unwrap().to_owned(),
-        vec![nft_b]
+        store.get_balances().get(&userb.pubkey()).copied(),
+        Some(b_before + FUNGIBLE_SUPPLY)
     );
 
+    // the accounting still adds up after binds, rebalances and redeems
+    let audit = simulate_view::<AuditView>(
+        &mut context,
+        &owner,
+        x404::instructions::audit_state(
+            source,
+            x404_state,
+            owner_store,
+            bound_registry,
+            fungible_mint,
+        ),
+    )
+    .await;
+    assert_eq!(audit.discrepancies, vec![]);
+    assert!(audit.bound > 0);
+
+    // a direct burn never reaches the hook either, the next transfer syncs
+    // the account and the NFT the burned tokens backed goes to the pool
+    let burn = spl_token_2022::instruction::burn(
+        &spl_token_2022::ID,
+        &a_second,
+        &fungible_mint,
+        &userb.pubkey(),
+        &[],
+        FUNGIBLE_SUPPLY - 100,
+    )
+    .unwrap();
+    execute(&mut context, &userb, &[burn], vec![&userb])
+        .await
+        .unwrap();
+    let b_assigned = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap()
+        .get_map()
+        .get(&userb.pubkey())
+        .unwrap()
+        .len();
+    test_transfer_between(
+        &mut context,
+        &userb,
+        a_second,
+        a_balance,
+        fungible_mint,
+        extra_account,
+        x404_state,
+        owner_store,
+        1,
+    )
+    .await
+    .unwrap();
+    let store = read_account::<OwnerStore>(&mut context, owner_store)
+        .await
+        .unwrap();
+    let b_live = read_account::<TokenAccount>(&mut context, b_balance)
+        .await
+        .unwrap()
+        .amount
+        + read_account::<TokenAccount>(&mut context, a_second)
+            .await
+            .unwrap()
+            .amount;
     assert_eq!(
-        owner_store_data.get(&userb.pubkey()).unwrap().to_owned(),
-        vec![]
+        store.get_balances().get(&userb.pubkey()).copied(),
+        Some(b_live)
     );
     assert_eq!(
-        owner_store_data.get(&x404_state).unwrap().to_owned(),
-        vec![nft_a, nft_c]
+        store.get_map().get(&userb.pubkey()).map_or(0, Vec::len),
+        b_assigned - 1
     );
-
-    assert_balance(
+    // only the deposit backing the burned tokens is missing
+    let audit = simulate_view::<AuditView>(
         &mut context,
-        a_balance,
-        FUNGIBLE_SUPPLY + FUNGIBLE_SUPPLY / 2 - REDEEMFEE,
+        &owner,
+        x404::instructions::audit_state(
+            source,
+            x404_state,
+            owner_store,
+            bound_registry,
+            fungible_mint,
+        ),
     )
     .await;
-    assert_balance(&mut context, b_balance, FUNGIBLE_SUPPLY / 2 + REDEEMFEE).await;
+    assert!(matches!(
+        audit.discrepancies.as_slice(),
+        [AuditDiscrepancy::FungibleSupply { .. }]
+    ));
+
+    // accounts written before the layouts were versioned are migrated in place
+    let legacy_source = Pubkey::new_unique();
+    let legacy_state = add_pda(&[b"state".as_ref(), legacy_source.as_ref()], ID);
+    let legacy_store = add_pda(&[b"owner_store".as_ref(), legacy_state.as_ref()], ID);
+    let legacy_bank = Pubkey::new_unique();
+    let legacy_nft = Pubkey::new_unique();
+    let legacy_holder = Pubkey::new_unique();
+
+    let mut hub_data = X404Hub::DISCRIMINATOR.to_vec();
+    hub_data.push(0);
+    hub_data.extend_from_slice(owner.pubkey().as_ref());
+    let mut state_data = X404State::DISCRIMINATOR.to_vec();
+    LegacyX404State {
+        source: legacy_source,
+        redeem_max_deadline: REDEEM_MAX_DEADLINE,
+        redeem_fee: REDEEMFEE,
+        owner: owner.pubkey(),
+        decimal: DECIMALS,
+        fungible_mint,
+        fungible_hook: extra_account,
+        collection_mint: Pubkey::new_unique(),
+        nft_supply: 1,
+        nft_in_use: 1,
+        fungible_supply: FUNGIBLE_SUPPLY,
+    }
+    .serialize(&mut state_data)
+    .unwrap();
+    let mut store_data = OwnerStore::DISCRIMINATOR.to_vec();
+    LegacyOwnerStore {
+        store: HashMap::from([(legacy_holder, vec![legacy_nft])])
+            .try_to_vec()
+            .unwrap(),
+    }
+    .serialize(&mut store_data)
+    .unwrap();
+    let mut bank_data = NFTBank::DISCRIMINATOR.to_vec();
+    bank_data.extend_from_slice(legacy_nft.as_ref());
+    bank_data.extend_from_slice(&0u64.to_le_bytes());
+    bank_data.extend_from_slice(legacy_holder.as_ref());
+    bank_data.push(1);
+    for (address, data) in [
+        (hub_state, hub_data),
+        (legacy_state, state_data),
+        (legacy_store, store_data),
+        (legacy_bank, bank_data),
+    ] {
+        let mut account = AccountSharedData::new(1_000_000_000, data.len(), &ID);
+        account.set_data_from_slice(&data);
+        context.set_account(&address, &account);
+    }
+
+    let migrate = x404::instructions::migrate(
+        legacy_source,
+        hub_state,
+        legacy_state,
+        legacy_store,
+        fungible_mint,
+        &[legacy_bank],
+        owner.pubkey(),
+    );
+    let migrate_by_user = x404::instructions::migrate(
+        legacy_source,
+        hub_state,
+        legacy_state,
+        legacy_store,
+        fungible_mint,
+        &[legacy_bank],
+        usera.pubkey(),
+    );
+    assert!(
+        execute(&mut context, &usera, &[migrate_by_user], vec![&usera])
+            .await
+            .is_err()
+    );
+    execute(
+        &mut context,
+        &owner,
+        std::slice::from_ref(&migrate),
+        vec![&owner],
+    )
+    .await
+    .unwrap();
 
-    let state_data = read_account::<X404State>(&mut context, x404_state)
+    let hub = read_account::<X404Hub>(&mut context, hub_state)
+        .await
+        .unwrap();
+    assert_eq!(hub.manager, owner.pubkey());
+    assert_eq!(hub.hook_programs, vec![]);
+    let migrated = read_account::<X404State>(&mut context, legacy_state)
+        .await
+        .unwrap();
+    assert_eq!(migrated.version, X404_STATE_VERSION);
+    assert_eq!(migrated.source, legacy_source);
+    assert_eq!(migrated.fungible_supply, FUNGIBLE_SUPPLY);
+    assert_eq!(migrated.hook_program, HookID);
+    assert_eq!(migrated.royalty_receiver, owner.pubkey());
+    let store = read_account::<OwnerStore>(&mut context, legacy_store)
+        .await
+        .unwrap();
+    assert_eq!(store.version, OWNER_STORE_VERSION);
+    assert_eq!(store.source, legacy_source);
+    assert_eq!(
+        store.get_index(),
+        HashMap::from([(legacy_nft, legacy_holder)])
+    );
+    assert!(store.get_balances().is_empty());
+    let bank = read_account::<NFTBank>(&mut context, legacy_bank)
         .await
         .unwrap();
+    assert_eq!(bank.owner, legacy_holder);
+    assert!(bank.issued);
+    assert_eq!(bank.linked_nft, Pubkey::default());
 
-    assert_eq!(state_data.nft_supply, 3);
-    assert_eq!(state_data.nft_in_use, 2);
+    // migrated accounts are left alone
+    execute(&mut context, &owner, &[migrate], vec![&owner])
+        .await
+        .unwrap();
+    let store = read_account::<OwnerStore>(&mut context, legacy_store)
+        .await
+        .unwrap();
+    assert_eq!(store.get_map().get(&legacy_holder), Some(&vec![legacy_nft]));
 }
 
 fn add_account(validator: &mut ProgramTest, amount: u64) -> Keypair {
@@ -443,10 +2228,73 @@ fn add_account(validator: &mut ProgramTest, amount: u64) -> Keypair {
     keypair
 }
 
+async fn read_token_metadata(context: &mut ProgramTestContext, mint: Pubkey) -> TokenMetadata {
+    let account = context
+        .banks_client
+        .get_account(mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mint = StateWithExtensions::<Mint>::unpack(&account.data).unwrap();
+    mint.get_variable_len_extension::<TokenMetadata>().unwrap()
+}
+
+async fn read_mint_extension<V: Extension + bytemuck::Pod>(
+    context: &mut ProgramTestContext,
+    mint: Pubkey,
+) -> V {
+    let account = context
+        .banks_client
+        .get_account(mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mint = StateWithExtensions::<Mint>::unpack(&account.data).unwrap();
+    *mint.get_extension::<V>().unwrap()
+}
+
+async fn read_mint(context: &mut ProgramTestContext, mint: Pubkey) -> Mint {
+    let account = context
+        .banks_client
+        .get_account(mint)
+        .await
+        .unwrap()
+        .unwrap();
+    StateWithExtensions::<Mint>::unpack(&account.data)
+        .unwrap()
+        .base
+}
+
+// merkle root and proofs of the reveal paths, their count is a power of two
+fn reveal_tree(paths: &[&str]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+    let mut level: Vec<[u8; 32]> = paths
+        .iter()
+        .enumerate()
+        .map(|(number, path)| reveal_leaf(number as u64, path))
+        .collect();
+    let mut proofs = vec![vec![]; paths.len()];
+    let mut width = 1;
+    while level.len() > 1 {
+        for (number, proof) in proofs.iter_mut().enumerate() {
+            proof.push(level[(number / width) ^ 1]);
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| reveal_parent(pair[0], pair[1]))
+            .collect();
+        width *= 2;
+    }
+    (level[0], proofs)
+}
+
 fn add_pda(seeds: &[&[u8]], id: Pubkey) -> Pubkey {
     Pubkey::find_program_address(seeds, &id).0
 }
 
+fn holder_config(state: Pubkey, owner: Pubkey) -> Pubkey {
+    add_pda(&[b"holder".as_ref(), state.as_ref(), owner.as_ref()], ID)
+}
+
 async fn transfer_lamports(
     context: &mut ProgramTestContext,
     payer: &Keypair,
@@ -507,19 +2355,57 @@ async fn create_spl_nft(
                 &mint.pubkey(),
                 &token,
                 &payer.pubkey(),
-                &[],
-                1,
+                &[],
+                1,
+            )
+            .unwrap(),
+        ],
+        vec![payer],
+    )
+    .await
+    .unwrap();
+
+    // skip freeze mint here.
+
+    Ok((mint.pubkey(), token))
+}
+
+async fn create_token_account(
+    context: &mut ProgramTestContext,
+    payer: &Keypair,
+    owner: Pubkey,
+    mint: Pubkey,
+) -> Result<Pubkey, BanksClientError> {
+    let account = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&[
+        ExtensionType::TransferHookAccount,
+    ])
+    .unwrap();
+    execute(
+        context,
+        payer,
+        &[
+            create_account(
+                &payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token_2022::ID,
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &spl_token_2022::ID,
+                &account.pubkey(),
+                &mint,
+                &owner,
             )
             .unwrap(),
         ],
-        vec![payer],
+        vec![payer, &account],
     )
-    .await
-    .unwrap();
+    .await?;
 
-    // skip freeze mint here.
-
-    Ok((mint.pubkey(), token))
+    Ok(account.pubkey())
 }
 
 async fn read_account<T: AccountDeserialize>(
@@ -550,6 +2436,30 @@ async fn execute(
     context.banks_client.process_transaction(transaction).await
 }
 
+async fn simulate_view<T: AnchorDeserialize>(
+    context: &mut ProgramTestContext,
+    payer: &Keypair,
+    instruction: Instruction,
+) -> T {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        context.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    let simulation = context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+
+    assert_eq!(return_data.program_id, ID);
+    T::try_from_slice(&return_data.data).unwrap()
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn test_init(
     mut context: &mut ProgramTestContext,
     owner: &Keypair,
@@ -575,9 +2485,107 @@ async fn test_init(
     assert_eq!(hub_data.manager, owner.pubkey());
     assert_eq!(hub_data.emergency_close, false);
 
-    // create x404
+    // create x404, the hook meta list must be the PDA of the hook program
+    let invalid_x404_instruction = x404::instructions::create_x404(
+        FUNGIBLE_NAME.to_string(),
+        FUNGIBLE_SYMBOL.to_string(),
+        FUNGIBLE_URI.to_string(),
+        REDEEMFEE,
+        REDEEM_MAX_DEADLINE,
+        DECIMALS,
+        hub_state,
+        source,
+        x404_state,
+        owner_store,
+        collection_mint,
+        fungible_mint,
+        owner.pubkey(),
+        owner_store,
+        HookID,
+        FUNGIBLE_SUPPLY,
+    );
+    // the hook program is not approved yet
+    assert!(execute(
+        &mut context,
+        &owner,
+        &[invalid_x404_instruction],
+        vec![&owner]
+    )
+    .await
+    .is_err());
+
+    execute(
+        context,
+        &owner,
+        &[x404::instructions::add_hook_program(
+            HookID,
+            hub_state,
+            owner.pubkey(),
+        )],
+        vec![&owner],
+    )
+    .await
+    .unwrap();
+
+    let hub_data = read_account::<X404Hub>(context, hub_state).await.unwrap();
+    assert_eq!(hub_data.hook_programs, vec![HookID]);
+
+    let invalid_x404_instruction = x404::instructions::create_x404(
+        FUNGIBLE_NAME.to_string(),
+        FUNGIBLE_SYMBOL.to_string(),
+        FUNGIBLE_URI.to_string(),
+        REDEEMFEE,
+        REDEEM_MAX_DEADLINE,
+        DECIMALS,
+        hub_state,
+        source,
+        x404_state,
+        owner_store,
+        collection_mint,
+        fungible_mint,
+        owner.pubkey(),
+        source,
+        HookID,
+        FUNGIBLE_SUPPLY,
+    );
+    assert!(execute(
+        &mut context,
+        &owner,
+        &[invalid_x404_instruction],
+        vec![&owner]
+    )
+    .await
+    .is_err());
+
+    // a NFT must be worth some fungible tokens
+    let invalid_x404_instruction = x404::instructions::create_x404(
+        FUNGIBLE_NAME.to_string(),
+        FUNGIBLE_SYMBOL.to_string(),
+        FUNGIBLE_URI.to_string(),
+        REDEEMFEE,
+        REDEEM_MAX_DEADLINE,
+        DECIMALS,
+        hub_state,
+        source,
+        x404_state,
+        owner_store,
+        collection_mint,
+        fungible_mint,
+        owner.pubkey(),
+        extra_account,
+        HookID,
+        0,
+    );
+    assert!(
+        execute(context, owner, &[invalid_x404_instruction], vec![owner])
+            .await
+            .is_err()
+    );
 
     let init_x404_instruction = x404::instructions::create_x404(
+        FUNGIBLE_NAME.to_string(),
+        FUNGIBLE_SYMBOL.to_string(),
+        FUNGIBLE_URI.to_string(),
         REDEEMFEE,
         REDEEM_MAX_DEADLINE,
         DECIMALS,
@@ -620,29 +2628,53 @@ async fn test_init(
 
     assert_eq!(fungible_mint_data.owner, token_2022::ID);
 
-    // add additional rent to owner_store and fungible mint
-    transfer_lamports(&mut context, owner, owner_store, 1_000_000_000)
-        .await
-        .unwrap();
-    transfer_lamports(&mut context, owner, fungible_mint, 1_000_000_000)
+    // the meta list is created together with the state
+    let extra_account_data = context
+        .banks_client
+        .get_account(extra_account)
         .await
+        .unwrap()
         .unwrap();
-    // initiate extra account
 
+    assert_eq!(extra_account_data.owner, HookID);
+
+    let metadata = read_token_metadata(context, fungible_mint).await;
+    assert_eq!(metadata.name, FUNGIBLE_NAME);
+    assert_eq!(metadata.symbol, FUNGIBLE_SYMBOL);
+    assert_eq!(metadata.uri, FUNGIBLE_URI);
+    assert_eq!(
+        Option::<Pubkey>::from(metadata.update_authority),
+        Some(x404_state)
+    );
+
+    // the manager can update the metadata, a longer value grows the mint
+    let new_uri = "https://example.com/collections/test_token/metadata.json";
     execute(
         context,
-        owner,
-        &[initialize_extra_account(
-            extra_account,
+        &owner,
+        &[x404::instructions::update_metadata(
+            FUNGIBLE_NAME.to_string(),
+            FUNGIBLE_SYMBOL.to_string(),
+            new_uri.to_string(),
+            source,
+            hub_state,
+            x404_state,
             fungible_mint,
             owner.pubkey(),
-            x404_state,
-            owner_store,
         )],
-        vec![owner],
+        vec![&owner],
     )
     .await
     .unwrap();
+
+    let metadata = read_token_metadata(context, fungible_mint).await;
+    assert_eq!(metadata.name, FUNGIBLE_NAME);
+    assert_eq!(metadata.uri, new_uri);
+
+    // add additional rent to the fungible mint
+    transfer_lamports(&mut context, owner, fungible_mint, 1_000_000_000)
+        .await
+        .unwrap();
     // create collection
 
     let init_collection_instruction = x404::instructions::mint_collection(
@@ -650,6 +2682,7 @@ async fn test_init(
         "test_nft".to_string(),
         "https://www.google.com/images/branding/googlelogo/1x/googlelogo_light_color_272x92dp.png"
             .to_string(),
+        COLLECTION_MAX_SIZE,
         source,
         x404_state,
         collection_mint,
@@ -665,9 +2698,49 @@ async fn test_init(
     )
     .await
     .unwrap();
+
+    let metadata = read_token_metadata(context, collection_mint).await;
+    assert_eq!(metadata.name, "test_nft");
+    assert_eq!(metadata.symbol, "test_nft");
+    assert_eq!(metadata.mint, collection_mint);
+    assert_eq!(
+        Option::<Pubkey>::from(metadata.update_authority),
+        Some(x404_state)
+    );
+    let collection_token = read_account::<TokenAccount>(context, nft_token)
+        .await
+        .unwrap();
+    assert_eq!(collection_token.amount, 1);
+    let group = read_mint_extension::<TokenGroup>(context, collection_mint).await;
+    assert_eq!(
+        Option::<Pubkey>::from(group.update_authority),
+        Some(x404_state)
+    );
+    assert_eq!(group.mint, collection_mint);
+    assert_eq!(u32::from(group.size), 0);
+    assert_eq!(u32::from(group.max_size), COLLECTION_MAX_SIZE);
+
+    // the collection can only be created once
+    let init_collection_instruction = x404::instructions::mint_collection(
+        "test_nft".to_string(),
+        "test_nft".to_string(),
+        "https://example.com/test_nft.json".to_string(),
+        COLLECTION_MAX_SIZE,
+        source,
+        x404_state,
+        collection_mint,
+        nft_token,
+        owner.pubkey(),
+    );
+    assert!(
+        execute(context, owner, &[init_collection_instruction], vec![owner])
+            .await
+            .is_err()
+    );
     println!("initiate accomplished");
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn test_deposit(
     mut context: &mut ProgramTestContext,
     source: Pubkey,
@@ -721,8 +2794,13 @@ async fn test_deposit(
         source,
         x404_state,
         owner_store,
+        add_pda(&[b"exemption".as_ref(), x404_state.as_ref()], ID),
+        holder_config(x404_state, user.pubkey()),
+        add_pda(&[b"collection_mint".as_ref(), x404_state.as_ref()], ID),
         nft_bank,
         nft_mint,
+        HookID,
+        get_extra_account_metas_address(&nft_mint, &HookID),
         fungible_mint,
         fungible_token,
         user.pubkey(),
@@ -736,6 +2814,7 @@ async fn test_deposit(
     Ok((nft_mint, deposit_mint))
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn test_transfer(
     context: &mut ProgramTestContext,
     sender: &Keypair,
@@ -746,7 +2825,6 @@ async fn test_transfer(
     owner_store: Pubkey,
     amount: u64,
 ) -> Result<(), BanksClientError> {
-    println!("start to transfer {amount}");
     let source = get_associated_token_address_with_program_id(
         &sender.pubkey(),
         &fungible_mint,
@@ -755,6 +2833,83 @@ async fn test_transfer(
     let destination =
         get_associated_token_address_with_program_id(receiver, &fungible_mint, &spl_token_2022::ID);
 
+    test_transfer_between(
+        context,
+        sender,
+        source,
+        destination,
+        fungible_mint,
+        extra_account,
+        state,
+        owner_store,
+        amount,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn test_nft_transfer(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    owner: &Keypair,
+    source: Pubkey,
+    destination: Pubkey,
+    nft_mint: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+) -> Result<(), BanksClientError> {
+    println!("start to transfer {nft_mint}");
+    let transfer_instruction = Instruction {
+        program_id: spl_token_2022::ID,
+        accounts: vec![
+            AccountMeta::new(source, false),
+            AccountMeta::new_readonly(nft_mint, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(authority.pubkey(), true),
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new_readonly(owner_store, false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new(add_pda(&[b"bound".as_ref(), state.as_ref()], ID), false),
+            AccountMeta::new_readonly(HookID, false),
+            AccountMeta::new_readonly(get_extra_account_metas_address(&nft_mint, &HookID), false),
+        ],
+        data: TokenInstruction::TransferChecked {
+            amount: 1,
+            decimals: 0,
+        }
+        .pack(),
+    };
+    execute(
+        context,
+        owner,
+        &[transfer_instruction],
+        vec![owner, authority],
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn test_transfer_between(
+    context: &mut ProgramTestContext,
+    sender: &Keypair,
+    source: Pubkey,
+    destination: Pubkey,
+    fungible_mint: Pubkey,
+    extra_account: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    println!("start to transfer {amount}");
+    let source_owner = read_account::<TokenAccount>(context, source)
+        .await
+        .unwrap()
+        .owner;
+    let destination_owner = read_account::<TokenAccount>(context, destination)
+        .await
+        .unwrap()
+        .owner;
+
     let transfer_instruction = Instruction {
         program_id: spl_token_2022::ID,
         accounts: vec![
@@ -762,11 +2917,14 @@ async fn test_transfer(
             AccountMeta::new(fungible_mint, false),
             AccountMeta::new(destination, false),
             AccountMeta::new(sender.pubkey(), true),
-            AccountMeta::new_readonly(state, false),
-            AccountMeta::new(owner_store, false),
             AccountMeta::new_readonly(AssociatedToken::id(), false),
             AccountMeta::new_readonly(ID, false),
             AccountMeta::new_readonly(Token2022::id(), false),
+            AccountMeta::new(owner_store, false),
+            AccountMeta::new_readonly(state, false),
+            AccountMeta::new_readonly(add_pda(&[b"exemption".as_ref(), state.as_ref()], ID), false),
+            AccountMeta::new_readonly(holder_config(state, source_owner), false),
+            AccountMeta::new_readonly(holder_config(state, destination_owner), false),
             AccountMeta::new_readonly(HookID, false),
             AccountMeta::new_readonly(extra_account, false),
         ],
@@ -779,15 +2937,127 @@ async fn test_transfer(
 
     println!("sender account: {}", source);
     println!("receiver account: {}", destination);
-    execute(context, &sender, &[transfer_instruction], vec![&sender])
-        .await
-        .unwrap();
+    execute(context, &sender, &[transfer_instruction], vec![&sender]).await?;
 
     println!("accomplished transfer {amount}");
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
+async fn test_delegated_transfer(
+    context: &mut ProgramTestContext,
+    owner: &Keypair,
+    delegate: &Keypair,
+    fungible_mint: Pubkey,
+    extra_account: Pubkey,
+    state: Pubkey,
+    owner_store: Pubkey,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    let source = get_associated_token_address_with_program_id(
+        &owner.pubkey(),
+        &fungible_mint,
+        &spl_token_2022::ID,
+    );
+    let destination = get_associated_token_address_with_program_id(
+        &delegate.pubkey(),
+        &fungible_mint,
+        &spl_token_2022::ID,
+    );
+
+    println!("start to approve {} for {amount}", delegate.pubkey());
+    let approve_instruction = spl_token_2022::instruction::approve(
+        &spl_token_2022::ID,
+        &source,
+        &delegate.pubkey(),
+        &owner.pubkey(),
+        &[],
+        amount,
+    )
+    .unwrap();
+    execute(context, owner, &[approve_instruction], vec![owner]).await?;
+
+    test_transfer_between(
+        context,
+        delegate,
+        source,
+        destination,
+        fungible_mint,
+        extra_account,
+        state,
+        owner_store,
+        amount,
+    )
+    .await
+}
+
+async fn test_exemption(
+    context: &mut ProgramTestContext,
+    manager: &Keypair,
+    source: Pubkey,
+    hub_state: Pubkey,
+    x404_state: Pubkey,
+    target: Pubkey,
+    exempt: bool,
+) -> Result<(), BanksClientError> {
+    println!("start to update exemption of {target}: {exempt}");
+    let owner_store = add_pda(&[b"owner_store".as_ref(), x404_state.as_ref()], ID);
+    let exemption = add_pda(&[b"exemption".as_ref(), x404_state.as_ref()], ID);
+
+    let instruction = if exempt {
+        x404::instructions::add_exemption(
+            target,
+            source,
+            hub_state,
+            x404_state,
+            owner_store,
+            exemption,
+            holder_config(x404_state, target),
+            manager.pubkey(),
+        )
+    } else {
+        x404::instructions::remove_exemption(
+            target,
+            source,
+            hub_state,
+            x404_state,
+            owner_store,
+            exemption,
+            holder_config(x404_state, target),
+            manager.pubkey(),
+        )
+    };
+
+    execute(context, manager, &[instruction], vec![manager]).await?;
+    println!("accomplished update exemption of {target}");
+    Ok(())
+}
+
+async fn test_skip_nft(
+    context: &mut ProgramTestContext,
+    source: Pubkey,
+    x404_state: Pubkey,
+    user: &Keypair,
+    skip: bool,
+) -> Result<(), BanksClientError> {
+    println!("start to set skip nft of {}: {skip}", user.pubkey());
+    let instruction = x404::instructions::set_skip_nft(
+        skip,
+        source,
+        x404_state,
+        add_pda(&[b"owner_store".as_ref(), x404_state.as_ref()], ID),
+        add_pda(&[b"exemption".as_ref(), x404_state.as_ref()], ID),
+        holder_config(x404_state, user.pubkey()),
+        user.pubkey(),
+    );
+
+    execute(context, user, &[instruction], vec![user]).await?;
+    println!("accomplished set skip nft of {}", user.pubkey());
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn test_bind(
     context: &mut ProgramTestContext,
     source: Pubkey,
@@ -817,6 +3087,7 @@ async fn test_bind(
         source,
         x404_state,
         owner_store,
+        add_pda(&[b"bound".as_ref(), x404_state.as_ref()], ID),
         bind_mint,
         bind_receiver,
         fungible_mint,
@@ -831,6 +3102,7 @@ async fn test_bind(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn test_unbind(
     context: &mut ProgramTestContext,
     source: Pubkey,
@@ -859,6 +3131,9 @@ async fn test_unbind(
         source,
         x404_state,
         owner_store,
+        add_pda(&[b"bound".as_ref(), x404_state.as_ref()], ID),
+        add_pda(&[b"exemption".as_ref(), x404_state.as_ref()], ID),
+        holder_config(x404_state, user.pubkey()),
         unbind_mint,
         unbind_holder,
         fungible_mint,
@@ -866,14 +3141,13 @@ async fn test_unbind(
         user.pubkey(),
     );
 
-    execute(context, &user, &[unbind_instruction], vec![&user])
-        .await
-        .unwrap();
+    execute(context, &user, &[unbind_instruction], vec![&user]).await?;
 
     println!("accomplish unbind {unbind_mint} for {source}");
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn test_redeem(
     context: &mut ProgramTestContext,
     source: Pubkey,
@@ -883,6 +3157,7 @@ async fn test_redeem(
     user: &Keypair,
     fungible_mint: Pubkey,
     old_owner: Pubkey,
+    linked_nft: Option<Pubkey>,
 ) -> Result<(), BanksClientError> {
     println!("start to redeem {withdraw_mint}");
     let withdraw_holder =
@@ -912,6 +3187,9 @@ async fn test_redeem(
         source,
         x404_state,
         owner_store,
+        add_pda(&[b"exemption".as_ref(), x404_state.as_ref()], ID),
+        holder_config(x404_state, user.pubkey()),
+        holder_config(x404_state, old_owner),
         withdraw_mint,
         withdraw_holder,
         withdraw_receiver,
@@ -919,6 +3197,7 @@ async fn test_redeem(
         original_owner_account,
         fungible_mint,
         user_account,
+        linked_nft,
         user.pubkey(),
     );
     execute(context, user, &[deposit_instructiona], vec![user])
@@ -929,6 +3208,38 @@ async fn test_redeem(
     Ok(())
 }
 
+// store a metaplex metadata account for a deposited mint
+fn add_deposit_metadata(
+    context: &mut ProgramTestContext,
+    deposit_mint: Pubkey,
+    uri: &str,
+) -> Pubkey {
+    let metadata_program = x404::state::TOKEN_METADATA_PROGRAM_ID;
+    let deposit_metadata = add_pda(
+        &[
+            b"metadata".as_ref(),
+            metadata_program.as_ref(),
+            deposit_mint.as_ref(),
+        ],
+        metadata_program,
+    );
+
+    // key, update authority, mint, then the padded name, symbol and uri
+    let mut data = vec![4];
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(deposit_mint.as_ref());
+    for (value, len) in [("deposit", 32), ("DEP", 10), (uri, 200)] {
+        let mut padded = value.as_bytes().to_vec();
+        padded.resize(len, 0);
+        data.extend_from_slice(&(len as u32).to_le_bytes());
+        data.extend_from_slice(&padded);
+    }
+    let mut account = AccountSharedData::new(1_000_000_000, data.len(), &metadata_program);
+    account.set_data_from_slice(&data);
+    context.set_account(&deposit_metadata, &account);
+    deposit_metadata
+}
+
 async fn assert_balance(mut context: &mut ProgramTestContext, account: Pubkey, expected: u64) {
     let balance = read_account::<TokenAccount>(&mut context, account)
         .await